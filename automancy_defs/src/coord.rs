@@ -45,6 +45,23 @@ impl TileCoord {
     pub const LEFT: Self = Self(TileHex::NEIGHBORS_COORDS[5]);
     pub const TOP_LEFT: Self = Self(TileHex::NEIGHBORS_COORDS[4]);
 
+    /// Rotates the coordinate around the origin by 60° per step. Positive steps rotate clockwise.
+    pub fn rotate(self, steps: i32) -> Self {
+        (0..steps.rem_euclid(6)).fold(self, |coord, _| Self(coord.0.clockwise()))
+    }
+
+    /// Mirrors the coordinate across the axis running from left to right through the origin, swapping the top and the bottom.
+    pub fn mirror(self) -> Self {
+        let (right, top_right) = (Self::RIGHT, Self::TOP_RIGHT);
+
+        // split the coordinate into steps to the right and to the top right, which flip over to the bottom right
+        let det = right.x * top_right.y - right.y * top_right.x;
+        let a = (self.x * top_right.y - self.y * top_right.x) / det;
+        let b = (right.x * self.y - right.y * self.x) / det;
+
+        right * a + Self::BOTTOM_RIGHT * b
+    }
+
     /// Creates a list of the neighbors
     pub fn neighbors(self) -> [Self; 6] {
         [
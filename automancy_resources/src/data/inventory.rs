@@ -34,6 +34,10 @@ impl Inventory {
         *self.0.entry(id).or_insert(0)
     }
 
+    pub fn contains(&self, id: Id, amount: ItemAmount) -> bool {
+        self.0.get(&id).is_some_and(|stored| *stored >= amount)
+    }
+
     pub fn get_mut(&mut self, id: Id) -> &mut ItemAmount {
         self.0.entry(id).or_insert(0)
     }
@@ -96,9 +100,23 @@ impl Inventory {
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Hash, Serialize, Deserialize)]
 pub struct InventoryRaw(Vec<(IdRaw, ItemAmount)>);
 
+impl Deref for InventoryRaw {
+    type Target = Vec<(IdRaw, ItemAmount)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for InventoryRaw {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 impl InventoryRaw {
     pub fn into_inner(self) -> Vec<(IdRaw, ItemAmount)> {
         self.0
@@ -175,7 +175,7 @@ impl Data {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct DataMap(BTreeMap<Id, Data>);
 
 impl From<DataMap> for BTreeMap<Id, Data> {
@@ -223,7 +223,7 @@ impl DataMap {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub enum DataRaw {
     Inventory(InventoryRaw),
     Coord(TileCoord),
@@ -248,7 +248,7 @@ impl DataRaw {
                     .flat_map(|id| interner.get(id.to_string()))
                     .collect(),
             ),
-            DataRaw::SetId(v) => Data::VecId(
+            DataRaw::SetId(v) => Data::SetId(
                 v.iter()
                     .flat_map(|id| interner.get(id.to_string()))
                     .collect(),
@@ -268,7 +268,7 @@ impl DataRaw {
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DataMapRaw(HashMap<String, DataRaw>);
 
 impl From<DataMapRaw> for HashMap<String, DataRaw> {
@@ -283,6 +283,20 @@ impl From<HashMap<String, DataRaw>> for DataMapRaw {
     }
 }
 
+impl Deref for DataMapRaw {
+    type Target = HashMap<String, DataRaw>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for DataMapRaw {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 impl DataMapRaw {
     pub fn into_inner(self) -> HashMap<String, DataRaw> {
         self.0
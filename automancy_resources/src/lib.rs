@@ -29,7 +29,7 @@ use crate::data::stack::{ItemAmount, ItemStack};
 use crate::error::ErrorManager;
 use crate::registry::{DataIds, ErrorIds, GuiIds, ModelIds, Registry};
 use crate::types::font::Font;
-use crate::types::function::RhaiDataMap;
+use crate::types::function::{RhaiDataMap, TileAction, WorldSnapshot};
 use crate::types::model::IndexRange;
 use crate::types::script::{Instructions, Script};
 use crate::types::tag::Tag;
@@ -51,6 +51,8 @@ pub enum LoadResourceError {
     InvalidFileError(PathBuf, &'static str),
     #[error("could not convert OsString to String")]
     OsStringError(PathBuf),
+    #[error("the research {0} is invalid: {1}")]
+    InvalidResearchError(String, String),
 }
 
 pub static RESOURCE_MAN: RwLock<Option<Arc<ResourceManager>>> = RwLock::new(None);
@@ -95,7 +97,8 @@ pub const IMAGE_EXT: &str = "png";
 /// Represents a resource manager, which contains all resources (apart from maps) loaded from disk dynamically.
 pub struct ResourceManager {
     pub interner: Interner,
-    pub track: TrackHandle,
+    /// The track sounds are played on, which is kept when the resources are reloaded.
+    pub track: Arc<TrackHandle>,
     pub error_man: ErrorManager,
     pub engine: Engine,
 
@@ -121,10 +124,11 @@ impl Debug for ResourceManager {
 }
 
 impl ResourceManager {
-    pub fn new(track: TrackHandle) -> Self {
+    pub fn new(track: Arc<TrackHandle>) -> Self {
         let mut interner = Interner::new();
         let none = id::NONE.to_id(&mut interner);
         let any = id_static("automancy", "#any").to_id(&mut interner);
+        let placeholder = id_static("automancy", "placeholder").to_id(&mut interner);
 
         let mut engine = Engine::new();
         engine.set_max_expr_depths(0, 0);
@@ -159,6 +163,8 @@ impl ResourceManager {
                 .register_fn("rotate_right", |n: TileCoord| {
                     TileCoord::from(n.clockwise())
                 })
+                .register_fn("rotate", TileCoord::rotate)
+                .register_fn("mirror", TileCoord::mirror)
                 .register_get("q", |v: &mut TileCoord| v.x)
                 .register_get("r", |v: &mut TileCoord| v.y)
                 .register_fn("+", TileCoord::add)
@@ -189,7 +195,11 @@ impl ResourceManager {
                 .register_iterator::<Vec<Id>>();
             engine
                 .register_type_with_name::<Script>("Script")
-                .register_get("instructions", |v: &mut Script| v.instructions.clone());
+                .register_get("instructions", |v: &mut Script| v.instructions.clone())
+                .register_get("adjacent", |v: &mut Script| match v.adjacent {
+                    Some(id) => Dynamic::from_int(id.into()),
+                    None => Dynamic::UNIT,
+                });
             engine
                 .register_type_with_name::<Instructions>("Instructions")
                 .register_get("inputs", |v: &mut Instructions| match &v.inputs {
@@ -215,9 +225,45 @@ impl ResourceManager {
                 .register_get("item", |v: &mut ItemStack| v.item)
                 .register_get("amount", |v: &mut ItemStack| v.amount);
             engine.register_type_with_name::<Tag>("Tag");
+            engine
+                .register_type_with_name::<TileAction>("TileAction")
+                .register_fn("to_string", |v: &mut TileAction| format!("{v:?}"))
+                .register_fn("PassOn", TileAction::PassOn)
+                .register_fn("Proxy", TileAction::Proxy)
+                .register_fn("Consume", TileAction::Consume)
+                .register_fn(
+                    "MakeTransaction",
+                    |coord: TileCoord, source_id: INT, source_coord: TileCoord, stacks: Dynamic| {
+                        TileAction::rhai_make_transaction(
+                            coord,
+                            source_id,
+                            source_coord,
+                            stacks,
+                            false,
+                        )
+                    },
+                )
+                .register_fn(
+                    "MakeHiddenTransaction",
+                    |coord: TileCoord, source_id: INT, source_coord: TileCoord, stacks: Dynamic| {
+                        TileAction::rhai_make_transaction(
+                            coord,
+                            source_id,
+                            source_coord,
+                            stacks,
+                            true,
+                        )
+                    },
+                )
+                .register_fn("MakeExtractRequest", TileAction::rhai_make_extract_request);
         }
 
         {
+            engine
+                .register_fn("tile_at", WorldSnapshot::rhai_tile_at)
+                .register_fn("data_of", WorldSnapshot::rhai_data_of)
+                .register_fn("neighbors", WorldSnapshot::rhai_neighbors)
+                .register_fn("is_adjacent", WorldSnapshot::rhai_is_adjacent);
             engine.register_fn("as_script", |id: INT| {
                 match RESOURCE_MAN
                     .read()
@@ -285,6 +331,15 @@ impl ResourceManager {
         let gui_ids = GuiIds::new(&mut interner);
         let err_ids = ErrorIds::new(&mut interner);
 
+        let tiles = HashMap::from([(
+            placeholder,
+            TileDef {
+                model: model_ids.missing,
+                function: None,
+                data: Default::default(),
+            },
+        )]);
+
         Self {
             interner,
             track,
@@ -292,7 +347,7 @@ impl ResourceManager {
             engine,
 
             registry: Registry {
-                tiles: Default::default(),
+                tiles,
                 scripts: Default::default(),
                 tags: Default::default(),
                 categories: Default::default(),
@@ -303,6 +358,7 @@ impl ResourceManager {
 
                 none,
                 any,
+                placeholder,
 
                 data_ids,
                 model_ids,
@@ -354,6 +410,13 @@ impl ResourceManager {
         }
     }
 
+    pub fn research_str(&self, id: &Id) -> &str {
+        match self.translates.research.get(id) {
+            Some(str) => str,
+            None => &self.translates.unnamed,
+        }
+    }
+
     pub fn tile_name(&self, id: &Id) -> &str {
         match self.translates.tiles.get(id) {
             Some(name) => name,
@@ -25,6 +25,8 @@ pub struct Registry {
 
     pub none: Id,
     pub any: Id,
+    /// the tile that stands in for tiles of a map that are missing from the game
+    pub placeholder: Id,
 
     pub data_ids: DataIds,
     pub model_ids: ModelIds,
@@ -54,6 +56,11 @@ pub struct DataIds {
     pub default_tile: Id,
     pub unlocked_researches: Id,
     pub category: Id,
+    pub seed: Id,
+    /// whether a map is in creative mode
+    pub creative: Id,
+    /// which of the map's missing tiles a placeholder tile stands in for
+    pub placeholder_key: Id,
 }
 
 #[derive(Copy, Clone, IdReg)]
@@ -82,9 +89,16 @@ pub struct GuiIds {
     pub debug_menu: Id,
     pub load_map: Id,
     pub delete_map: Id,
+    pub rename_map: Id,
+    pub duplicate_map: Id,
     pub create_map: Id,
     pub invalid_name: Id,
     pub options: Id,
+    pub production_stats: Id,
+    pub research_menu: Id,
+    pub missing_ids: Id,
+    pub map_backups: Id,
+    pub map_description: Id,
 
     pub hint_search_script: Id,
     pub hint_search_item: Id,
@@ -95,6 +109,28 @@ pub struct GuiIds {
     pub lbl_pick_another_name: Id,
     pub lbl_delete_map_confirm: Id,
     pub lbl_cannot_place_missing_item: Id,
+    pub lbl_last_second: Id,
+    pub lbl_last_minute: Id,
+    pub lbl_last_hour: Id,
+    pub lbl_produced: Id,
+    pub lbl_consumed: Id,
+    pub lbl_moved: Id,
+    pub lbl_stats_by_item: Id,
+    pub lbl_stats_by_tile: Id,
+    pub lbl_research_cost: Id,
+    pub lbl_research_unlocks: Id,
+    pub lbl_research_unlocked: Id,
+    pub lbl_research_unavailable: Id,
+    pub lbl_missing_ids: Id,
+    pub lbl_keep_placeholder: Id,
+    pub lbl_leave_out: Id,
+    pub lbl_no_backups: Id,
+    pub lbl_map_codec: Id,
+    pub lbl_map_name: Id,
+    pub lbl_creative: Id,
+    pub lbl_play_time: Id,
+    pub lbl_map_summary: Id,
+    pub lbl_script_adjacent: Id,
 
     pub btn_confirm: Id,
     pub btn_exit: Id,
@@ -107,7 +143,14 @@ pub struct GuiIds {
     pub btn_unpause: Id,
     pub btn_load: Id,
     pub btn_delete: Id,
+    pub btn_backups: Id,
+    pub btn_restore: Id,
+    pub btn_describe: Id,
+    pub btn_duplicate: Id,
+    pub btn_fill: Id,
     pub btn_new_map: Id,
+    pub btn_unlock: Id,
+    pub btn_reload_resources: Id,
 
     pub time_fmt: Id,
 }
@@ -117,6 +160,22 @@ pub struct GuiIds {
 pub struct ErrorIds {
     /// This error is displayed when the map cannot be read.
     pub invalid_map_data: Id,
+    /// This error is displayed when the map was saved by a newer version of the game.
+    pub unsupported_map_version: Id,
+    /// This error is displayed when the map cannot be written.
+    pub unwritable_map: Id,
     /// This error is displayed when the options cannot be written.
     pub unwritable_options: Id,
+    /// This error is displayed when a pasted blueprint cannot be read.
+    pub invalid_blueprint: Id,
+    /// This error is displayed when a pasted blueprint uses IDs that do not exist.
+    pub unknown_blueprint_ids: Id,
+    /// This error is displayed when a blueprint is not placed, as it uses tiles that do not exist.
+    pub unknown_blueprint_tiles: Id,
+    /// This error is displayed when a blueprint is not placed, as it uses tiles that are locked behind a research.
+    pub locked_blueprint_tiles: Id,
+    /// This error is displayed when a blueprint is not placed, as the player is missing the items for its tiles.
+    pub missing_blueprint_items: Id,
+    /// This error is displayed when the resources cannot be reloaded.
+    pub unloadable_resources: Id,
 }
@@ -24,7 +24,7 @@ impl ResourceManager {
 
                 if let Ok(audio) = StaticSoundData::from_file(
                     &file,
-                    StaticSoundSettings::default().output_destination(&self.track),
+                    StaticSoundSettings::default().output_destination(&*self.track),
                 ) {
                     let name = file
                         .file_stem()
@@ -1,22 +1,32 @@
 use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fs::read_dir;
+use std::mem;
 use std::path::Path;
+use std::sync::Arc;
 
-use rhai::{Dynamic, ImmutableString, Scope};
+use hashbrown::HashMap;
+use rhai::{Dynamic, EvalAltResult, ImmutableString, NativeCallContext, Scope, INT};
 
+use automancy_defs::coord::TileCoord;
 use automancy_defs::id::{Id, IdRaw};
 use automancy_defs::log;
 
+use crate::data::item::item_match;
+use crate::data::stack::{ItemAmount, ItemStack};
 use crate::data::{Data, DataMap};
-use crate::{ResourceManager, FUNCTION_EXT};
+use crate::{ResourceManager, FUNCTION_EXT, RESOURCE_MAN};
 
 #[derive(Default, Debug, Clone)]
-pub struct RhaiDataMap(BTreeMap<Id, Dynamic>);
+pub struct RhaiDataMap {
+    values: BTreeMap<Id, Dynamic>,
+    /// Whether a value was set or removed since the last time this was checked.
+    changed: bool,
+}
 
 impl RhaiDataMap {
     pub fn rhai_get(&mut self, id: Id) -> Dynamic {
-        if let Some(v) = self.0.get(&id).cloned() {
+        if let Some(v) = self.values.get(&id).cloned() {
             v.cast::<Data>().rhai_value()
         } else {
             Dynamic::UNIT
@@ -25,31 +35,37 @@ impl RhaiDataMap {
 
     pub fn rhai_set(&mut self, id: Id, v: Dynamic) {
         if let Some(v) = Data::from_rhai(v) {
-            self.0.insert(id, Dynamic::from(v));
+            self.set(id, v);
         }
     }
 
     pub fn rhai_get_or_new_inventory(&mut self, id: Id) -> Dynamic {
-        self.0
-            .entry(id)
-            .or_insert_with(|| Dynamic::from(Data::Inventory(Default::default())));
+        if !self.values.contains_key(&id) {
+            self.set(id, Data::Inventory(Default::default()));
+        }
         self.rhai_get(id)
     }
 
     pub fn get(&self, id: Id) -> Option<Data> {
-        self.0.get(&id).map(|v| v.clone_cast())
+        self.values.get(&id).map(|v| v.clone_cast())
     }
 
     pub fn set(&mut self, id: Id, data: Data) {
-        self.0.insert(id, Dynamic::from(data));
+        self.values.insert(id, Dynamic::from(data));
+        self.changed = true;
     }
 
     pub fn remove(&mut self, id: Id) {
-        self.0.remove(&id);
+        self.changed |= self.values.remove(&id).is_some();
+    }
+
+    /// Whether a value was set or removed since the last time this was called.
+    pub fn take_changed(&mut self) -> bool {
+        mem::take(&mut self.changed)
     }
 
     pub fn to_data_map(self) -> DataMap {
-        self.0
+        self.values
             .into_iter()
             .map(|(k, mut v)| (k, v.take().cast::<Data>()))
             .collect::<BTreeMap<Id, Data>>()
@@ -57,12 +73,148 @@ impl RhaiDataMap {
     }
 
     pub fn from_data_map(map: DataMap) -> Self {
-        RhaiDataMap(
-            map.into_inner()
+        RhaiDataMap {
+            values: map
+                .into_inner()
                 .into_iter()
                 .map(|(k, v)| (k, Dynamic::from(v)))
                 .collect(),
-        )
+            changed: false,
+        }
+    }
+}
+
+/// A read-only view of the tiles of a map and their data, taken by the game at the start of each tick.
+///
+/// Functions are called with it as their tag, which `tile_at`, `data_of`, `neighbors` and `is_adjacent` query.
+/// Changes made during the tick only show up in the next snapshot.
+///
+/// The game keeps the snapshot and changes only the tiles that changed in it, which copies it only if something
+/// still holds on to the last one. The data of each tile is shared until it changes.
+#[derive(Debug, Clone, Default)]
+pub struct WorldSnapshot {
+    pub tiles: HashMap<TileCoord, Id>,
+    pub data: HashMap<TileCoord, Arc<DataMap>>,
+}
+
+impl WorldSnapshot {
+    fn from_context(context: &NativeCallContext) -> Option<Arc<WorldSnapshot>> {
+        context.tag()?.clone().try_cast::<Arc<WorldSnapshot>>()
+    }
+
+    /// Gets the ID of the tile at the coordinates, or nothing if there is none.
+    pub fn rhai_tile_at(context: NativeCallContext, coord: TileCoord) -> Dynamic {
+        Self::from_context(&context)
+            .and_then(|world| world.tiles.get(&coord).cloned())
+            .map(|id| Dynamic::from_int(id.into()))
+            .unwrap_or(Dynamic::UNIT)
+    }
+
+    /// Gets a data value of the tile at the coordinates, or nothing if there is none.
+    pub fn rhai_data_of(context: NativeCallContext, coord: TileCoord, key: Id) -> Dynamic {
+        Self::from_context(&context)
+            .and_then(|world| world.data.get(&coord)?.get(&key).cloned())
+            .map(Data::rhai_value)
+            .unwrap_or(Dynamic::UNIT)
+    }
+
+    /// Gets the coordinates around the given ones that have a tile.
+    pub fn rhai_neighbors(context: NativeCallContext, coord: TileCoord) -> rhai::Array {
+        let Some(world) = Self::from_context(&context) else {
+            return vec![];
+        };
+
+        coord
+            .neighbors()
+            .into_iter()
+            .filter(|coord| world.tiles.contains_key(coord))
+            .map(Dynamic::from)
+            .collect()
+    }
+
+    /// Whether any tile around the coordinates is the given tile, or has the given tag.
+    pub fn rhai_is_adjacent(context: NativeCallContext, coord: TileCoord, id: INT) -> bool {
+        let Some(world) = Self::from_context(&context) else {
+            return false;
+        };
+        let resource_man = RESOURCE_MAN.read().unwrap();
+        let resource_man = resource_man.as_ref().unwrap();
+
+        coord.neighbors().into_iter().any(|coord| {
+            world
+                .tiles
+                .get(&coord)
+                .is_some_and(|tile| item_match(resource_man, *tile, id.into()))
+        })
+    }
+}
+
+/// What a function asks its tile to do, returned from its handlers.
+///
+/// Each variant has a constructor of the same name in Rhai, e.g. `PassOn(coord)`.
+#[derive(Debug, Clone)]
+pub enum TileAction {
+    /// Passes the transferred stack on to the tile at the coordinates, as the new source.
+    PassOn(TileCoord),
+    /// Passes the transferred stack on to the tile at the coordinates, keeping the original source.
+    Proxy(TileCoord),
+    /// Takes the given amount of the transferred stack.
+    Consume(ItemAmount),
+    /// Sends the stacks to the tile at `coord`, as coming from the given source.
+    /// Hidden transactions are not shown as moving items.
+    MakeTransaction {
+        coord: TileCoord,
+        source_id: Id,
+        source_coord: TileCoord,
+        stacks: Vec<ItemStack>,
+        hidden: bool,
+    },
+    /// Asks the tile at `coord` to send items to the given tile.
+    MakeExtractRequest {
+        coord: TileCoord,
+        requested_from_id: Id,
+        requested_from_coord: TileCoord,
+    },
+}
+
+impl TileAction {
+    /// Creates a transaction from Rhai, where the stacks are either an array of ItemStack or a list of them taken from a script.
+    pub fn rhai_make_transaction(
+        coord: TileCoord,
+        source_id: INT,
+        source_coord: TileCoord,
+        stacks: Dynamic,
+        hidden: bool,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let stacks = if stacks.is::<Vec<ItemStack>>() {
+            stacks.cast::<Vec<ItemStack>>()
+        } else {
+            let ty = stacks.type_name();
+
+            stacks
+                .into_typed_array::<ItemStack>()
+                .map_err(|_| format!("expected an array of ItemStack, found {ty}"))?
+        };
+
+        Ok(TileAction::MakeTransaction {
+            coord,
+            source_id: source_id.into(),
+            source_coord,
+            stacks,
+            hidden,
+        })
+    }
+
+    pub fn rhai_make_extract_request(
+        coord: TileCoord,
+        requested_from_id: INT,
+        requested_from_coord: TileCoord,
+    ) -> Self {
+        TileAction::MakeExtractRequest {
+            coord,
+            requested_from_id: requested_from_id.into(),
+            requested_from_coord,
+        }
     }
 }
 
@@ -1,33 +1,54 @@
 use std::ffi::OsStr;
 use std::fs::read_to_string;
+use std::mem;
 use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 
+use automancy_defs::graph::algo::is_cyclic_directed;
+use automancy_defs::graph::graph::NodeIndex;
 use automancy_defs::graph::visit::IntoNodeReferences;
+use automancy_defs::graph::Direction;
 use automancy_defs::id::{Id, IdRaw};
 use automancy_defs::log;
 
-use crate::{load_recursively, ResourceManager, RON_EXT};
+use crate::data::stack::ItemAmount;
+use crate::data::{Data, DataMap};
+use crate::{load_recursively, LoadResourceError, ResourceManager, RON_EXT};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ResearchRaw {
     id: IdRaw,
-    unlock: IdRaw,
     icon: IdRaw,
     next: Option<Vec<IdRaw>>,
     name: IdRaw,
     description: IdRaw,
+    #[serde(default)]
+    required_items: Vec<(IdRaw, ItemAmount)>,
+    #[serde(default)]
+    depends_on: Vec<IdRaw>,
+    #[serde(default)]
+    unlocks: Vec<IdRaw>,
+    /// Deprecated, researches written before `unlocks` unlock only this.
+    #[serde(default)]
+    unlock: Option<IdRaw>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Research {
     pub id: Id,
-    pub unlock: Id,
     pub icon: Id,
     pub next: Option<Vec<Id>>,
     pub name: Id,
     pub description: Id,
+    /// The items taken from the player inventory to unlock the research.
+    pub required_items: Vec<(Id, ItemAmount)>,
+    /// The researches that all need to be unlocked before this one.
+    pub depends_on: Vec<Id>,
+    /// The tiles, scripts and categories this research unlocks.
+    pub unlocks: Vec<Id>,
+    /// What the research unlocks if it was written before `unlocks`. Folded into `unlocks` when the researches are compiled.
+    pub unlock: Option<Id>,
 }
 
 impl ResourceManager {
@@ -37,24 +58,41 @@ impl ResourceManager {
         let research: ResearchRaw = ron::from_str(&read_to_string(file)?)?;
 
         let id = research.id.to_id(&mut self.interner);
-        let unlock = research.unlock.to_id(&mut self.interner);
         let icon = research.icon.to_id(&mut self.interner);
         let next = research
             .next
             .map(|v| v.iter().map(|id| id.to_id(&mut self.interner)).collect());
         let name = research.name.to_id(&mut self.interner);
         let description = research.description.to_id(&mut self.interner);
+        let required_items = research
+            .required_items
+            .iter()
+            .map(|(id, amount)| (id.to_id(&mut self.interner), *amount))
+            .collect();
+        let depends_on = research
+            .depends_on
+            .iter()
+            .map(|id| id.to_id(&mut self.interner))
+            .collect();
+        let unlocks = research
+            .unlocks
+            .iter()
+            .map(|id| id.to_id(&mut self.interner))
+            .collect();
+        let unlock = research.unlock.map(|id| id.to_id(&mut self.interner));
 
         let index = self.registry.researches.add_node(Research {
             id,
-            unlock,
             next,
             icon,
             name,
             description,
+            required_items,
+            depends_on,
+            unlocks,
+            unlock,
         });
         self.registry.researches_id_map.insert(id, index);
-        self.registry.researches_unlock_map.insert(unlock, index);
 
         Ok(())
     }
@@ -83,15 +121,212 @@ impl ResourceManager {
             .and_then(|i| self.registry.researches.node_weight(*i))
     }
 
-    pub fn compile_researches(&mut self) {
+    /// Links the researches together and fills in what they unlock, making sure everything they refer to exists and that they do not depend on themselves.
+    pub fn compile_researches(&mut self) -> anyhow::Result<()> {
+        for index in self.registry.researches.node_indices().collect::<Vec<_>>() {
+            let research = &mut self.registry.researches[index];
+
+            if let Some(id) = research.unlock.take() {
+                log::warn!(
+                    "Research {} uses the deprecated unlock field, use unlocks instead",
+                    self.interner.resolve(research.id).unwrap_or_default()
+                );
+
+                if !research.unlocks.contains(&id) {
+                    research.unlocks.push(id);
+                }
+            }
+        }
+
         for (index, research) in self.registry.researches.clone().node_references() {
-            if let Some(next) = &research.next {
-                for id in next {
-                    if let Some(next_index) = self.registry.researches_id_map.get(id).cloned() {
-                        self.registry.researches.add_edge(index, next_index, ());
-                    }
+            let invalid = |reason: String| {
+                LoadResourceError::InvalidResearchError(
+                    self.interner
+                        .resolve(research.id)
+                        .unwrap_or_default()
+                        .to_string(),
+                    reason,
+                )
+            };
+            let name = |id: &Id| self.interner.resolve(*id).unwrap_or_default().to_string();
+
+            for id in research.next.iter().flatten() {
+                let Some(next_index) = self.registry.researches_id_map.get(id).cloned() else {
+                    return Err(
+                        invalid(format!("the next research {} does not exist", name(id))).into(),
+                    );
+                };
+
+                self.registry.researches.update_edge(index, next_index, ());
+            }
+
+            for id in &research.depends_on {
+                let Some(parent_index) = self.registry.researches_id_map.get(id).cloned() else {
+                    return Err(invalid(format!(
+                        "the research it depends on, {}, does not exist",
+                        name(id)
+                    ))
+                    .into());
+                };
+
+                self.registry
+                    .researches
+                    .update_edge(parent_index, index, ());
+            }
+
+            for id in &research.unlocks {
+                if !(self.registry.tiles.contains_key(id)
+                    || self.registry.scripts.contains_key(id)
+                    || self.registry.categories.contains_key(id))
+                {
+                    return Err(invalid(format!(
+                        "the unlocked {} is not a tile, script or category",
+                        name(id)
+                    ))
+                    .into());
                 }
+
+                if let Some(other) = self.registry.researches_unlock_map.insert(*id, index) {
+                    return Err(invalid(format!(
+                        "{} is already unlocked by {}",
+                        name(id),
+                        name(&self.registry.researches[other].id)
+                    ))
+                    .into());
+                }
+            }
+
+            for (id, amount) in &research.required_items {
+                if !self.registry.items.contains_key(id) {
+                    return Err(
+                        invalid(format!("the required item {} does not exist", name(id))).into(),
+                    );
+                }
+
+                if *amount <= 0 {
+                    return Err(invalid(format!(
+                        "the required amount of {} is not positive",
+                        name(id)
+                    ))
+                    .into());
+                }
+            }
+        }
+
+        if is_cyclic_directed(&self.registry.researches) {
+            return Err(LoadResourceError::InvalidResearchError(
+                "<all>".to_string(),
+                "the researches depend on each other in a cycle".to_string(),
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the research at the given index has been unlocked in the game data.
+    pub fn is_research_unlocked(&self, index: NodeIndex, game_data: &DataMap) -> bool {
+        let Some(research) = self.registry.researches.node_weight(index) else {
+            return false;
+        };
+
+        // older maps loaded the unlocked researches as a list
+        match game_data.get(&self.registry.data_ids.unlocked_researches) {
+            Some(Data::SetId(unlocked)) => unlocked.contains(&research.id),
+            Some(Data::VecId(unlocked)) => unlocked.contains(&research.id),
+            _ => false,
+        }
+    }
+
+    /// Returns whether every research leading to the one at the given index has been unlocked.
+    pub fn is_research_available(&self, index: NodeIndex, game_data: &DataMap) -> bool {
+        self.registry
+            .researches
+            .neighbors_directed(index, Direction::Incoming)
+            .all(|parent| self.is_research_unlocked(parent, game_data))
+    }
+
+    /// Returns whether the tile or script with the given ID can be used, which is when no research unlocks it, or that research has been unlocked.
+    /// Tiles also need the research unlocking their category, if there is one.
+    pub fn is_unlocked(&self, id: Id, game_data: &DataMap) -> bool {
+        let category = self
+            .registry
+            .tiles
+            .get(&id)
+            .and_then(|tile| tile.data.get(&self.registry.data_ids.category))
+            .cloned()
+            .and_then(Data::into_id);
+
+        [Some(id), category].into_iter().flatten().all(|id| {
+            match self.registry.researches_unlock_map.get(&id) {
+                Some(index) => self.is_research_unlocked(*index, game_data),
+                None => true,
+            }
+        })
+    }
+
+    /// Returns whether the player inventory has the items required by the research at the given index.
+    pub fn has_research_items(&self, index: NodeIndex, game_data: &DataMap) -> bool {
+        let Some(research) = self.registry.researches.node_weight(index) else {
+            return false;
+        };
+
+        if research.required_items.is_empty() {
+            return true;
+        }
+
+        if let Some(Data::Inventory(inventory)) =
+            game_data.get(&self.registry.data_ids.player_inventory)
+        {
+            research
+                .required_items
+                .iter()
+                .all(|(item, amount)| inventory.contains(*item, *amount))
+        } else {
+            false
+        }
+    }
+
+    /// Unlocks the research with the given ID, taking the required items from the player inventory.
+    /// Returns false and leaves the game data untouched if the research is not available, or the player does not have the items.
+    pub fn unlock_research(&self, id: Id, game_data: &mut DataMap) -> bool {
+        let Some(index) = self.registry.researches_id_map.get(&id).cloned() else {
+            return false;
+        };
+
+        if self.is_research_unlocked(index, game_data)
+            || !self.is_research_available(index, game_data)
+        {
+            return false;
+        }
+
+        if !self.has_research_items(index, game_data) {
+            return false;
+        }
+
+        let research = &self.registry.researches[index];
+
+        if let Some(Data::Inventory(inventory)) =
+            game_data.get_mut(&self.registry.data_ids.player_inventory)
+        {
+            for (item, amount) in &research.required_items {
+                inventory.take(*item, *amount);
             }
         }
+
+        let unlocked = game_data
+            .entry(self.registry.data_ids.unlocked_researches)
+            .or_insert_with(|| Data::SetId(Default::default()));
+
+        if let Data::VecId(ids) = unlocked {
+            let ids = mem::take(ids);
+            *unlocked = Data::SetId(ids.into_iter().collect());
+        }
+
+        if let Data::SetId(unlocked) = unlocked {
+            unlocked.insert(research.id);
+        }
+
+        true
     }
 }
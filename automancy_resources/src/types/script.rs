@@ -26,6 +26,8 @@ pub struct InstructionsRaw {
 #[derive(Debug, Clone)]
 pub struct Script {
     pub id: Id,
+    /// The tile, or tag of tiles, that needs to be next to the tile running the script.
+    pub adjacent: Option<Id>,
     pub instructions: Instructions,
 }
 
@@ -42,6 +44,7 @@ impl ResourceManager {
         let script: ScriptRaw = ron::from_str(&read_to_string(file)?)?;
 
         let id = script.id.to_id(&mut self.interner);
+        let adjacent = script.adjacent.map(|id| id.to_id(&mut self.interner));
 
         let instructions = Instructions {
             inputs: script.instructions.inputs.map(|v| {
@@ -69,7 +72,11 @@ impl ResourceManager {
                 .collect(),
         };
 
-        let script = Script { id, instructions };
+        let script = Script {
+            id,
+            adjacent,
+            instructions,
+        };
 
         self.registry.scripts.insert(id, script);
 
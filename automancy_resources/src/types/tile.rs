@@ -60,7 +60,13 @@ impl ResourceManager {
     }
 
     pub fn ordered_tiles(&mut self) {
-        let mut ids = self.registry.tiles.keys().cloned().collect::<Vec<_>>();
+        let mut ids = self
+            .registry
+            .tiles
+            .keys()
+            .filter(|id| **id != self.registry.placeholder)
+            .cloned()
+            .collect::<Vec<_>>();
 
         ids.sort_by_key(|id| self.tile_name(id));
 
@@ -19,6 +19,7 @@ pub struct TranslateRaw {
     tiles: HashMap<IdRaw, String>,
     categories: HashMap<IdRaw, String>,
     scripts: HashMap<IdRaw, String>,
+    research: HashMap<IdRaw, String>,
     gui: HashMap<IdRaw, String>,
     error: HashMap<IdRaw, String>,
 }
@@ -31,6 +32,7 @@ pub struct Translate {
     pub tiles: HashMap<Id, SharedStr>,
     pub categories: HashMap<Id, SharedStr>,
     pub scripts: HashMap<Id, SharedStr>,
+    pub research: HashMap<Id, SharedStr>,
     pub gui: HashMap<Id, SharedStr>,
     pub error: HashMap<Id, SharedStr>,
 }
@@ -64,6 +66,11 @@ impl ResourceManager {
             .into_iter()
             .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
             .collect();
+        let research = translate
+            .research
+            .into_iter()
+            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
+            .collect();
         let gui = translate
             .gui
             .into_iter()
@@ -82,6 +89,7 @@ impl ResourceManager {
             tiles,
             categories,
             scripts,
+            research,
             gui,
             error,
         };
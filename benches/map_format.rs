@@ -0,0 +1,102 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hashbrown::HashMap;
+
+use automancy::map::{MapCodec, MapRaw};
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{id, Id};
+use automancy_resources::data::DataRaw;
+
+/// The width and height of the benchmarked map, in tiles.
+const SIZE: i32 = 256;
+
+/// A map full of tiles that each point at their neighbor, like a large factory would.
+fn map() -> MapRaw {
+    let node = Id::from(0i64);
+    let merger = Id::from(1i64);
+
+    let tiles = (0..SIZE)
+        .flat_map(|q| (0..SIZE).map(move |r| TileCoord::new(q, r)))
+        .map(|coord| {
+            let id = if (coord.x + coord.y) % 2 == 0 {
+                node
+            } else {
+                merger
+            };
+
+            let data = HashMap::from([
+                (
+                    "automancy:target".to_string(),
+                    DataRaw::Coord(TileCoord::RIGHT),
+                ),
+                ("automancy:amount".to_string(), DataRaw::Amount(64)),
+            ]);
+
+            (coord, id, data.into())
+        })
+        .collect();
+
+    MapRaw {
+        map_name: "bench".to_string(),
+        tiles,
+        tile_map: HashMap::from([
+            (node, id("automancy", "node")),
+            (merger, id("automancy", "merger")),
+        ]),
+    }
+}
+
+fn map_format(c: &mut Criterion) {
+    let map = map();
+    let chunks = map.clone().into_chunks();
+
+    let mut group = c.benchmark_group("map_format");
+    group.sample_size(10);
+
+    for codec in [MapCodec::Ron, MapCodec::Bincode] {
+        let encoded = map.encode(Vec::new(), codec).unwrap();
+        let encoded_chunks = chunks
+            .values()
+            .map(|chunk| chunk.encode(Vec::new(), codec).unwrap())
+            .collect::<Vec<_>>();
+
+        group.bench_function(format!("{codec:?}/save single file"), |b| {
+            b.iter(|| map.encode(Vec::new(), codec).unwrap())
+        });
+        group.bench_function(format!("{codec:?}/save every chunk"), |b| {
+            b.iter(|| {
+                for chunk in chunks.values() {
+                    black_box(chunk.encode(Vec::new(), codec).unwrap());
+                }
+            })
+        });
+        group.bench_function(format!("{codec:?}/save one changed chunk"), |b| {
+            b.iter(|| {
+                for chunk in chunks.values() {
+                    black_box(chunk.content_hash());
+                }
+
+                chunks
+                    .values()
+                    .next()
+                    .unwrap()
+                    .encode(Vec::new(), codec)
+                    .unwrap()
+            })
+        });
+        group.bench_function(format!("{codec:?}/load single file"), |b| {
+            b.iter(|| MapRaw::decode(encoded.as_slice(), codec).unwrap())
+        });
+        group.bench_function(format!("{codec:?}/load chunks"), |b| {
+            b.iter(|| {
+                for chunk in &encoded_chunks {
+                    black_box(MapRaw::decode(chunk.as_slice(), codec).unwrap());
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, map_format);
+criterion_main!(benches);
@@ -22,7 +22,7 @@ use winit::window::{Fullscreen, Icon, WindowBuilder};
 
 use automancy::camera::Camera;
 use automancy::event::{on_event, EventLoopStorage};
-use automancy::game::load_map;
+use automancy::game::{load_map, GameMsg};
 use automancy::gpu::{init_gpu_resources, Gpu, DEPTH_FORMAT};
 use automancy::map::MAIN_MENU;
 use automancy::renderer::Renderer;
@@ -202,7 +202,12 @@ fn main() -> eyre::Result<()> {
 
     // load the main menu
     runtime
-        .block_on(load_map(&setup, &mut loop_store, MAIN_MENU.to_string()))
+        .block_on(load_map(
+            &setup,
+            &mut loop_store,
+            MAIN_MENU.to_string(),
+            Default::default(),
+        ))
         .unwrap();
 
     event_loop.run(move |event, target| {
@@ -259,6 +264,17 @@ fn main() -> eyre::Result<()> {
                 renderer.gpu.window.set_fullscreen(None);
             }
 
+            setup
+                .game
+                .send_message(GameMsg::SetUndoDepth(setup.options.game.undo_depth))
+                .unwrap();
+            setup
+                .game
+                .send_message(GameMsg::SetAutosaveInterval(Duration::from_secs(
+                    setup.options.game.autosave_interval * 60,
+                )))
+                .unwrap();
+
             setup.options.synced = true;
         }
 
@@ -0,0 +1,149 @@
+use std::env;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context};
+use env_logger::Env;
+use tokio::runtime::Runtime;
+
+use automancy::game::GameMsg;
+use automancy::map::Map;
+use automancy::setup::{load_resources, spawn_game};
+use automancy_defs::log;
+use automancy_resources::error::{error_to_key, error_to_string};
+use automancy_resources::kira::manager::backend::mock::MockBackend;
+use automancy_resources::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_resources::kira::track::TrackBuilder;
+use automancy_resources::RESOURCE_MAN;
+
+static USAGE: &str = "usage: automancy_headless <map name> [--ticks <count>] [--save]";
+
+/// The command line arguments of the headless runner.
+struct Args {
+    /// the name of the map to run, as found under the map folder
+    map_name: String,
+    /// how many ticks to run the map for
+    ticks: u64,
+    /// whether to save the map after ticking
+    save: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut map_name = None;
+        let mut ticks = 0;
+        let mut save = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ticks" => {
+                    let count = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--ticks requires a count\n{USAGE}"))?;
+
+                    ticks = count.parse()?;
+                }
+                "--save" => {
+                    save = true;
+                }
+                "--help" | "-h" => {
+                    bail!("{USAGE}");
+                }
+                _ if map_name.is_none() => {
+                    map_name = Some(arg);
+                }
+                _ => {
+                    bail!("unexpected argument {arg}\n{USAGE}");
+                }
+            }
+        }
+
+        Ok(Self {
+            map_name: map_name.ok_or_else(|| anyhow!("no map name given\n{USAGE}"))?,
+            ticks,
+            save,
+        })
+    }
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
+    if !Map::path(&args.map_name).is_dir() {
+        bail!("map {} does not exist", args.map_name);
+    }
+
+    let saved = Map::read_header(&args.map_name)
+        .with_context(|| format!("could not read the header of map {}", args.map_name))?;
+
+    // the mock backend never touches an audio device, the track only exists so sounds can be loaded
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default())?;
+    let track = audio_man.add_sub_track(TrackBuilder::new())?;
+
+    log::info!("Loading resources...");
+    let (resource_man, _, _) = load_resources(Arc::new(track));
+    RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
+    log::info!("Loaded resources.");
+
+    let (game, game_handle) = spawn_game(resource_man.clone()).await?;
+
+    game.send_message(GameMsg::LoadMap(args.map_name.clone(), Default::default()))?;
+
+    // a map that fails to load is swapped for an empty one, which would tick without any errors
+    let (_, loaded_name) = game.call(GameMsg::GetMapInfoAndName, None).await?.unwrap();
+    let tile_count = game.call(GameMsg::GetTileCount, None).await?.unwrap();
+
+    if loaded_name != args.map_name || tile_count as u64 != saved.tile_count {
+        game.stop(None);
+        game_handle.await?;
+
+        bail!(
+            "map {} did not load: {tile_count} of its {} tiles were loaded",
+            args.map_name,
+            saved.tile_count
+        );
+    }
+
+    log::info!("Running map {} for {} ticks...", args.map_name, args.ticks);
+    for _ in 0..args.ticks {
+        game.send_message(GameMsg::Tick)?;
+    }
+
+    // every tick is fully handled before the game handles the next message
+    let (info, _) = game.call(GameMsg::GetMapInfoAndName, None).await?.unwrap();
+    log::info!(
+        "Finished ticking. Map data: {}",
+        ron::to_string(&info.lock().await.data.to_raw(&resource_man.interner))?
+    );
+
+    if args.save {
+        game.call(GameMsg::SaveMap, None).await?.unwrap();
+    }
+
+    game.stop(Some("Headless run finished".to_string()));
+    game_handle.await?;
+
+    let mut failed = false;
+    while let Some(error) = resource_man.error_man.pop() {
+        log::error!(
+            "{}: {}",
+            error_to_key(&error, &resource_man),
+            error_to_string(&error, &resource_man)
+        );
+
+        failed = true;
+    }
+
+    if failed {
+        bail!("errors occurred while running map {}", args.map_name);
+    }
+
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    let args = Args::parse(env::args().skip(1))?;
+
+    let runtime = Runtime::new()?;
+
+    runtime.block_on(run(args))
+}
@@ -0,0 +1,237 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use env_logger::Env;
+use ron::ser::PrettyConfig;
+
+use automancy::map::{Map, MapRaw};
+use automancy::migration::migrate;
+use automancy::remap::collect_missing_ids;
+use automancy_resources::kira::manager::backend::mock::MockBackend;
+use automancy_resources::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_resources::kira::track::TrackBuilder;
+use automancy_resources::{ResourceManager, RESOURCES_PATH};
+
+static USAGE: &str = "usage:
+    automancy_map list
+    automancy_map dump <map name> [--json]
+    automancy_map pack <map name> <file>
+    automancy_map validate <map name> [--resources <dir>]
+
+dump prints all the tiles of a map, and pack replaces them with the ones in the file,
+which is read as JSON if it ends in .json and as RON otherwise.";
+
+/// What the map tool was asked to do.
+enum Command {
+    /// list every map in the map folder
+    List,
+    /// print the tiles of a map
+    Dump { map_name: String, json: bool },
+    /// replace the tiles of a map with the ones in a file
+    Pack { map_name: String, file: PathBuf },
+    /// check that every ID a map uses exists in a resources folder
+    Validate {
+        map_name: String,
+        resources: PathBuf,
+    },
+}
+
+impl Command {
+    fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let command = args
+            .next()
+            .ok_or_else(|| anyhow!("no command given\n{USAGE}"))?;
+
+        let mut positional = Vec::new();
+        let mut json = false;
+        let mut resources = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => {
+                    json = true;
+                }
+                "--resources" => {
+                    let dir = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--resources requires a folder\n{USAGE}"))?;
+
+                    resources = Some(PathBuf::from(dir));
+                }
+                "--help" | "-h" => {
+                    bail!("{USAGE}");
+                }
+                _ => {
+                    positional.push(arg);
+                }
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let mut next = |what: &str| {
+            positional
+                .next()
+                .ok_or_else(|| anyhow!("no {what} given\n{USAGE}"))
+        };
+
+        let parsed = match command.as_str() {
+            "list" => Command::List,
+            "dump" => Command::Dump {
+                map_name: next("map name")?,
+                json,
+            },
+            "pack" => Command::Pack {
+                map_name: next("map name")?,
+                file: PathBuf::from(next("file")?),
+            },
+            "validate" => Command::Validate {
+                map_name: next("map name")?,
+                resources: resources.unwrap_or_else(|| PathBuf::from(RESOURCES_PATH)),
+            },
+            "--help" | "-h" => bail!("{USAGE}"),
+            _ => bail!("unknown command {command}\n{USAGE}"),
+        };
+
+        if let Some(arg) = positional.next() {
+            bail!("unexpected argument {arg}\n{USAGE}");
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn check_exists(map_name: &str) -> anyhow::Result<()> {
+    if !Map::path(map_name).is_dir() {
+        bail!("map {map_name} does not exist");
+    }
+
+    Ok(())
+}
+
+fn list() -> anyhow::Result<()> {
+    let mut maps = fs::read_dir(Map::root())?
+        .flatten()
+        .map(|f| f.file_name().to_string_lossy().to_string())
+        .filter(|f| !f.starts_with('.'))
+        .collect::<Vec<_>>();
+
+    maps.sort();
+
+    for map_name in maps {
+        match Map::read_header(&map_name) {
+            Ok(info) => println!(
+                "{map_name}\tversion {}\t{:?}\t{} tiles",
+                info.version, info.codec, info.tile_count
+            ),
+            Err(e) => println!("{map_name}\tunreadable: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn dump(map_name: &str, json: bool) -> anyhow::Result<()> {
+    check_exists(map_name)?;
+
+    let saved = Map::read_header(map_name)?;
+    let map = Map::read_all_tiles(map_name, &saved)?;
+
+    let dumped = if json {
+        serde_json::to_string_pretty(&map)?
+    } else {
+        ron::ser::to_string_pretty(&map, PrettyConfig::default())?
+    };
+
+    println!("{dumped}");
+
+    Ok(())
+}
+
+fn pack(map_name: &str, file: &Path) -> anyhow::Result<()> {
+    check_exists(map_name)?;
+
+    let contents = fs::read_to_string(file)?;
+    let mut map: MapRaw = if file.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents)?
+    } else {
+        ron::from_str(&contents)?
+    };
+    map.map_name = map_name.to_string();
+
+    // the packed tiles are expected to be in the format dump prints, which is always the current version
+    let mut info = Map::read_header(map_name)?;
+    migrate(&mut info, &mut MapRaw::default())?;
+
+    let tile_count = map.tiles.len();
+    Map::replace_tiles(map_name, info, map)?;
+
+    println!("Packed {tile_count} tiles into map {map_name}");
+
+    Ok(())
+}
+
+fn validate(map_name: &str, resources: &Path) -> anyhow::Result<()> {
+    check_exists(map_name)?;
+
+    // the mock backend never touches an audio device, the track only exists to build the resource manager
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default())?;
+    let track = audio_man.add_sub_track(TrackBuilder::new())?;
+
+    let mut resource_man = ResourceManager::new(Arc::new(track));
+
+    for dir in fs::read_dir(resources)?.flatten().map(|v| v.path()) {
+        if !dir.is_dir() {
+            continue;
+        }
+
+        resource_man.load_tiles(&dir)?;
+        resource_man.load_items(&dir)?;
+        resource_man.load_tags(&dir)?;
+        resource_man.load_scripts(&dir)?;
+        resource_man.load_researches(&dir)?;
+    }
+
+    let saved = Map::read_header(map_name)?;
+    let map = Map::read_all_tiles(map_name, &saved)?;
+
+    let mut info = saved;
+    migrate(&mut info, &mut MapRaw::default())?;
+
+    let missing = collect_missing_ids(&resource_man, &info, &map);
+
+    if missing.is_empty() {
+        println!(
+            "Every ID in map {map_name} exists in {}",
+            resources.display()
+        );
+
+        return Ok(());
+    }
+
+    for (id, kind) in &missing {
+        println!("missing {kind:?}: {id}");
+    }
+
+    bail!(
+        "map {map_name} uses {} IDs that do not exist in {}",
+        missing.len(),
+        resources.display()
+    );
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_env(Env::default().default_filter_or("warn")).init();
+
+    match Command::parse(env::args().skip(1))? {
+        Command::List => list(),
+        Command::Dump { map_name, json } => dump(&map_name, json),
+        Command::Pack { map_name, file } => pack(&map_name, &file),
+        Command::Validate {
+            map_name,
+            resources,
+        } => validate(&map_name, &resources),
+    }
+}
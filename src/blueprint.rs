@@ -0,0 +1,279 @@
+use std::collections::BTreeSet;
+use std::io::Read;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use zstd::{Decoder, Encoder};
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Id, IdRaw, Interner};
+use automancy_defs::log;
+use automancy_resources::data::{Data, DataMap, DataMapRaw, DataRaw};
+use automancy_resources::ResourceManager;
+
+/// The start of every exported blueprint, to tell blueprints apart from other text.
+pub const BLUEPRINT_PREFIX: &str = "automancy-blueprint:";
+
+/// The tiles of a blueprint, as (position, tile ID, tile data).
+pub type BlueprintTiles = Vec<(TileCoord, Id, DataMap)>;
+
+/// A group of tiles and their data, copied out of a map to be pasted elsewhere.
+/// The positions, and the positions stored in the data, are relative to where the blueprint was copied from.
+#[derive(Debug, Clone, Default)]
+pub struct Blueprint {
+    pub tiles: BlueprintTiles,
+}
+
+/// A blueprint as it is stored in text, in the same layout as the tiles of a map.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlueprintRaw {
+    pub tiles: Vec<(TileCoord, Id, DataMapRaw)>,
+    pub tile_map: HashMap<Id, IdRaw>,
+}
+
+/// A rotation in 60° steps, optionally after mirroring, around an origin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// the steps to rotate clockwise by
+    pub rotation: i32,
+    /// whether the top and the bottom are swapped before rotating
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    /// Applies the orientation to a coordinate relative to the origin.
+    pub fn apply(self, coord: TileCoord) -> TileCoord {
+        let coord = if self.mirrored { coord.mirror() } else { coord };
+
+        coord.rotate(self.rotation)
+    }
+
+    /// Gets the orientation that undoes this one. Mirrored orientations undo themselves.
+    pub fn inverse(self) -> Self {
+        if self.mirrored {
+            self
+        } else {
+            Self {
+                rotation: -self.rotation,
+                mirrored: false,
+            }
+        }
+    }
+}
+
+/// Applies the functions to the coordinates stored in the tile data.
+/// `direction` is given the directions relative to the tile, like `target` and the entries of a [`Data::VecCoord`],
+/// and `position` the positions on the map, like `link`.
+fn map_data_coords(
+    resource_man: &ResourceManager,
+    data: &mut DataMap,
+    direction: impl Fn(TileCoord) -> TileCoord,
+    position: impl Fn(TileCoord) -> TileCoord,
+) {
+    if let Some(Data::Coord(target)) = data.get_mut(&resource_man.registry.data_ids.target) {
+        *target = direction(*target);
+    }
+
+    for value in data.values_mut() {
+        if let Data::VecCoord(coords) = value {
+            coords.iter_mut().for_each(|v| *v = direction(*v));
+        }
+    }
+
+    if let Some(Data::Coord(link)) = data.get_mut(&resource_man.registry.data_ids.link) {
+        *link = position(*link);
+    }
+}
+
+/// Adds the names of the data keys, and of the IDs stored in the data, that do not exist in the game to `unknown`.
+/// These are what gets left out when the data is converted.
+fn collect_unknown_ids(interner: &Interner, data: &DataMapRaw, unknown: &mut BTreeSet<String>) {
+    let mut check = |id: &IdRaw| {
+        if interner.get(id.to_string()).is_none() {
+            unknown.insert(id.to_string());
+        }
+    };
+
+    for (key, value) in data.iter() {
+        check(&IdRaw::parse(key));
+
+        match value {
+            DataRaw::Inventory(inventory) => {
+                for (id, _) in inventory.iter() {
+                    check(id);
+                }
+            }
+            DataRaw::Id(id) => check(id),
+            DataRaw::VecId(ids) | DataRaw::SetId(ids) => {
+                for id in ids {
+                    check(id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Blueprint {
+    /// Creates a blueprint out of the tiles copied from a map, with the given position as its origin.
+    pub fn new(resource_man: &ResourceManager, tiles: BlueprintTiles, origin: TileCoord) -> Self {
+        let tiles = tiles
+            .into_iter()
+            .map(|(coord, id, mut data)| {
+                map_data_coords(resource_man, &mut data, |v| v, |v| v - origin);
+
+                (coord - origin, id, data)
+            })
+            .collect();
+
+        Self { tiles }
+    }
+
+    /// Rotates and mirrors the blueprint around its origin, along with the directions stored in the data.
+    pub fn transform(&mut self, resource_man: &ResourceManager, orientation: Orientation) {
+        for (coord, _, data) in &mut self.tiles {
+            *coord = orientation.apply(*coord);
+
+            map_data_coords(
+                resource_man,
+                data,
+                |v| orientation.apply(v),
+                |v| orientation.apply(v),
+            );
+        }
+    }
+
+    /// Rotates the blueprint around its origin by 60° per step. Positive steps rotate clockwise.
+    pub fn rotate(&mut self, resource_man: &ResourceManager, steps: i32) {
+        self.transform(
+            resource_man,
+            Orientation {
+                rotation: steps,
+                mirrored: false,
+            },
+        );
+    }
+
+    /// Mirrors the blueprint across the axis running from left to right through its origin.
+    pub fn mirror(&mut self, resource_man: &ResourceManager) {
+        self.transform(
+            resource_man,
+            Orientation {
+                rotation: 0,
+                mirrored: true,
+            },
+        );
+    }
+
+    /// Gets the tiles to place when pasting the blueprint with its origin at the given position.
+    pub fn placed_at(&self, resource_man: &ResourceManager, at: TileCoord) -> BlueprintTiles {
+        self.tiles
+            .iter()
+            .cloned()
+            .map(|(coord, id, mut data)| {
+                map_data_coords(resource_man, &mut data, |v| v, |v| v + at);
+
+                (coord + at, id, data)
+            })
+            .collect()
+    }
+
+    /// Converts the blueprint into its raw form, with the IDs resolved to their names.
+    pub fn to_raw(&self, interner: &Interner) -> BlueprintRaw {
+        let mut raw = BlueprintRaw {
+            tiles: vec![],
+            tile_map: Default::default(),
+        };
+
+        for (coord, id, data) in &self.tiles {
+            let Some(name) = interner.resolve(*id) else {
+                continue;
+            };
+
+            raw.tile_map
+                .entry(*id)
+                .or_insert_with(|| IdRaw::parse(name));
+            raw.tiles.push((*coord, *id, data.to_raw(interner)));
+        }
+
+        raw
+    }
+
+    /// Converts a raw blueprint back, leaving out the tiles that do not exist.
+    /// Returns the names of every tile and data ID that could not be found, along with the blueprint.
+    pub fn from_raw(resource_man: &ResourceManager, raw: BlueprintRaw) -> (Self, BTreeSet<String>) {
+        let mut unknown = BTreeSet::new();
+        let mut tiles = vec![];
+
+        for (coord, id, data) in raw.tiles {
+            let Some(name) = raw.tile_map.get(&id) else {
+                continue;
+            };
+
+            collect_unknown_ids(&resource_man.interner, &data, &mut unknown);
+
+            match resource_man
+                .interner
+                .get(name.to_string())
+                .filter(|id| resource_man.registry.tiles.contains_key(id))
+            {
+                Some(id) => {
+                    let mut data = data.to_data(&resource_man.interner);
+
+                    // which tile a placeholder stands in for is only known to the game it was copied from
+                    if id == resource_man.registry.placeholder {
+                        data.remove(&resource_man.registry.data_ids.placeholder_key);
+                    }
+
+                    tiles.push((coord, id, data));
+                }
+                None => {
+                    unknown.insert(name.to_string());
+                }
+            }
+        }
+
+        (Self { tiles }, unknown)
+    }
+
+    /// Exports the blueprint as text, compressed with zstd and encoded in base64.
+    pub fn export(&self, interner: &Interner) -> anyhow::Result<String> {
+        let mut encoder = Encoder::new(vec![], 0)?;
+        ron::ser::to_writer(&mut encoder, &self.to_raw(interner))?;
+        let bytes = encoder.finish()?;
+
+        Ok(format!("{BLUEPRINT_PREFIX}{}", STANDARD.encode(bytes)))
+    }
+
+    /// Imports a blueprint exported with [`Blueprint::export`].
+    /// The IDs that do not exist in the game are reported to the error manager.
+    pub fn import(resource_man: &ResourceManager, text: &str) -> anyhow::Result<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix(BLUEPRINT_PREFIX).unwrap_or(text);
+
+        let bytes = STANDARD.decode(text)?;
+        let mut decoded = String::new();
+        Decoder::new(bytes.as_slice())?.read_to_string(&mut decoded)?;
+
+        let raw: BlueprintRaw = ron::from_str(&decoded)?;
+        let (blueprint, unknown) = Self::from_raw(resource_man, raw);
+
+        if !unknown.is_empty() {
+            let unknown = unknown.into_iter().collect::<Vec<_>>().join(", ");
+
+            log::warn!("Blueprint has unknown IDs: {unknown}");
+
+            resource_man.error_man.push(
+                (
+                    resource_man.registry.err_ids.unknown_blueprint_ids,
+                    vec![unknown],
+                ),
+                resource_man,
+            );
+        }
+
+        Ok(blueprint)
+    }
+}
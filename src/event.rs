@@ -1,9 +1,10 @@
 use std::collections::VecDeque;
 use std::mem;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use arboard::Clipboard;
 use egui::{LayerId, Rect};
 use egui_wgpu::wgpu::SurfaceError;
 use fuse_rust::Fuse;
@@ -25,16 +26,19 @@ use automancy_defs::math::{Float, Matrix4, FAR, HEX_GRID_LAYOUT};
 use automancy_defs::rendering::{make_line, InstanceData};
 use automancy_defs::{colors, log, math, window};
 use automancy_resources::data::item::Item;
+use automancy_resources::data::stack::ItemAmount;
 use automancy_resources::data::{Data, DataMap};
 use automancy_resources::kira::manager::AudioManager;
 use automancy_resources::ResourceManager;
 
-use crate::game::{GameMsg, PlaceTileResponse};
-use crate::gpu::AnimationMap;
+use crate::blueprint::{Blueprint, Orientation, BLUEPRINT_PREFIX};
+use crate::game::{GameMsg, PlaceBlueprintResponse, PlaceTileResponse};
+use crate::gpu::{AnimationMap, GlobalBuffers};
 use crate::gui;
+use crate::gui::popup::ReplacementCandidates;
 use crate::gui::{
-    debug, error, info, menu, player, popup, tile_config, tile_selection, GameEguiCallback,
-    GuiState, PopupState, Screen, TextField,
+    debug, error, info, menu, player, popup, research, statistics, tile_config, tile_selection,
+    GameEguiCallback, GuiState, PopupState, Screen, TextField,
 };
 use crate::input;
 use crate::input::KeyActions;
@@ -53,6 +57,8 @@ pub struct EventLoopStorage {
     pub already_placed_at: Option<TileCoord>,
     /// the tile that has its config menu open.
     pub config_open_at: Option<TileCoord>,
+    /// the amount in the tile config from before it started being dragged or typed in.
+    pub config_amount_before: Option<ItemAmount>,
     /// tag searching cache
     pub tag_cache: HashMap<Id, Arc<Vec<Item>>>,
     /// tile currently linking
@@ -69,6 +75,10 @@ pub struct EventLoopStorage {
     /// TODO shift is only on keyboard
     pub placement_direction: Option<TileCoord>,
     pub prev_placement_direction: Option<TileCoord>,
+    /// the last copied blueprint
+    pub blueprint: Option<Blueprint>,
+    /// whether the blueprint is being pasted
+    pub pasting: bool,
 
     pub take_item_animations: HashMap<Item, VecDeque<(Instant, Rect)>>,
 
@@ -79,8 +89,15 @@ pub struct EventLoopStorage {
 
     pub pointing_cache: Arc<Mutex<Option<(Id, ActorRef<TileEntityMsg>)>>>,
     pub pointing_updating: Arc<AtomicBool>,
+    /// bumped when the caches are forgotten, so that the updates started before then drop what they bring back
+    pub cache_generation: Arc<AtomicU64>,
 
     pub gui_state: GuiState,
+    /// what the missing IDs can be replaced with, while the popup for them is open
+    pub missing_id_candidates: Option<ReplacementCandidates>,
+
+    /// whether the resources are reloaded at the start of the next frame
+    pub reload_resources: bool,
 }
 
 impl Default for EventLoopStorage {
@@ -90,6 +107,7 @@ impl Default for EventLoopStorage {
             selected_tile_id: None,
             already_placed_at: None,
             config_open_at: None,
+            config_amount_before: None,
             tag_cache: Default::default(),
             linking_tile: None,
             frame_start: Instant::now(),
@@ -98,6 +116,8 @@ impl Default for EventLoopStorage {
             initial_cursor_position: None,
             placement_direction: None,
             prev_placement_direction: None,
+            blueprint: None,
+            pasting: false,
             take_item_animations: Default::default(),
 
             map_info: None,
@@ -107,13 +127,75 @@ impl Default for EventLoopStorage {
 
             pointing_cache: Arc::new(Default::default()),
             pointing_updating: Arc::new(Default::default()),
+            cache_generation: Arc::new(Default::default()),
 
             gui_state: Default::default(),
+            missing_id_candidates: None,
+
+            reload_resources: false,
         }
     }
 }
 
-impl EventLoopStorage {}
+impl EventLoopStorage {
+    /// Forgets everything that refers to tiles and items by their IDs, as the IDs do not match the ones of reloaded resources.
+    /// The updates still running are not waited for, and what they bring back is dropped.
+    pub fn forget_resources(&mut self) {
+        self.cache_generation.fetch_add(1, Ordering::SeqCst);
+
+        *self.config_open_cache.blocking_lock() = None;
+        *self.pointing_cache.blocking_lock() = None;
+
+        self.selected_tile_id = None;
+        self.already_placed_at = None;
+        self.tag_cache.clear();
+        self.missing_id_candidates = None;
+        self.blueprint = None;
+        self.pasting = false;
+        self.take_item_animations.clear();
+
+        self.gui_state.tile_selection_category = None;
+        self.gui_state.selected_research = None;
+    }
+}
+
+/// Loads the resources again and swaps them in for the game, the renderer and the GUI.
+fn reload_resources(
+    runtime: &Runtime,
+    setup: &mut GameSetup,
+    loop_store: &mut EventLoopStorage,
+    renderer: &mut Renderer,
+    gui: &mut Gui,
+) {
+    let (vertices, indices) = match runtime.block_on(setup.reload_resources()) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("Could not reload resources: {e:?}");
+
+            setup.resource_man.error_man.push(
+                (
+                    setup.resource_man.registry.err_ids.unloadable_resources,
+                    vec![format!("{e:#}")],
+                ),
+                &setup.resource_man,
+            );
+
+            return;
+        }
+    };
+
+    let global_buffers = Arc::new(GlobalBuffers::new(&renderer.gpu.device, vertices, indices));
+
+    renderer.global_buffers = global_buffers.clone();
+    renderer.forget_game_info();
+
+    gui.renderer.callback_resources.insert(global_buffers);
+    gui.renderer
+        .callback_resources
+        .insert(setup.resource_man.clone());
+
+    loop_store.forget_resources();
+}
 
 pub async fn shutdown_graceful(
     setup: &mut GameSetup,
@@ -141,6 +223,10 @@ fn render(
     gui: &mut Gui,
     target: &EventLoopWindowTarget<()>,
 ) -> anyhow::Result<bool> {
+    if mem::take(&mut loop_store.reload_resources) {
+        reload_resources(runtime, setup, loop_store, renderer, gui);
+    }
+
     gui::reset_callback_counter();
 
     let mut result = Ok(false);
@@ -171,6 +257,8 @@ fn render(
             if !loop_store.config_open_updating.load(Ordering::Relaxed) {
                 let cache = loop_store.config_open_cache.clone();
                 let updating = loop_store.config_open_updating.clone();
+                let generation = loop_store.cache_generation.clone();
+                let started_in = generation.load(Ordering::SeqCst);
                 let game = setup.game.clone();
 
                 updating.store(true, Ordering::Relaxed);
@@ -188,7 +276,11 @@ fn render(
                         .unwrap()
                         .unwrap();
 
-                    *cache.lock().await = tile.zip(entity);
+                    let mut cache = cache.lock().await;
+                    if generation.load(Ordering::SeqCst) == started_in {
+                        *cache = tile.zip(entity);
+                    }
+                    drop(cache);
 
                     updating.store(false, Ordering::Relaxed);
                 });
@@ -198,6 +290,8 @@ fn render(
         if !loop_store.pointing_updating.load(Ordering::Relaxed) {
             let cache = loop_store.pointing_cache.clone();
             let updating = loop_store.pointing_updating.clone();
+            let generation = loop_store.cache_generation.clone();
+            let started_in = generation.load(Ordering::SeqCst);
             let game = setup.game.clone();
             let pointing_at = setup.camera.pointing_at;
 
@@ -216,7 +310,11 @@ fn render(
                     .unwrap()
                     .unwrap();
 
-                *cache.lock().await = tile.zip(entity);
+                let mut cache = cache.lock().await;
+                if generation.load(Ordering::SeqCst) == started_in {
+                    *cache = tile.zip(entity);
+                }
+                drop(cache);
 
                 updating.store(false, Ordering::Relaxed);
             });
@@ -274,6 +372,7 @@ fn render(
 
                             if let Ok(id) = selection_recv.blocking_recv() {
                                 loop_store.already_placed_at = None;
+                                loop_store.pasting = false;
 
                                 if loop_store.selected_tile_id == Some(id) {
                                     loop_store.selected_tile_id = None;
@@ -283,6 +382,11 @@ fn render(
                             }
                         }
 
+                        // statistics, drawn after the map info is unlocked since it waits on the game
+                        if setup.input_handler.key_active(KeyActions::Statistics) {
+                            statistics::statistics(runtime, setup, loop_store, &gui.context);
+                        }
+
                         let cursor_pos = math::screen_to_world(
                             window::window_size_double(renderer.gpu.window),
                             setup.input_handler.main_pos,
@@ -371,7 +475,13 @@ fn render(
                 Screen::Paused => {
                     menu::pause_menu(runtime, setup, &gui.context, loop_store);
                 }
-                Screen::Research => {}
+                Screen::Research => {
+                    if let Some(map_info) = loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+                        let mut lock = map_info.blocking_lock();
+
+                        research::research_menu(setup, loop_store, &gui.context, &mut lock.data);
+                    }
+                }
             }
         }
         match loop_store.gui_state.popup.clone() {
@@ -383,6 +493,21 @@ fn render(
             PopupState::InvalidName => {
                 popup::invalid_name_popup(setup, gui, loop_store);
             }
+            PopupState::MapBackups(map_name) => {
+                popup::map_backups_popup(setup, gui, loop_store, &map_name);
+            }
+            PopupState::MapDescription(map_name) => {
+                popup::map_description_popup(setup, gui, loop_store, &map_name);
+            }
+            PopupState::MapRename(map_name) => {
+                popup::map_rename_popup(setup, gui, loop_store, &map_name);
+            }
+            PopupState::MapDuplicate(map_name) => {
+                popup::map_duplicate_popup(setup, gui, loop_store, &map_name);
+            }
+            PopupState::MissingIds(map_name) => {
+                popup::missing_ids_popup(runtime, setup, gui, loop_store, &map_name);
+            }
         }
 
         tile_tints.insert(setup.camera.pointing_at, colors::RED.with_alpha(0.2));
@@ -416,6 +541,33 @@ fn render(
             }
         }
 
+        if loop_store.pasting {
+            if let Some(blueprint) = &loop_store.blueprint {
+                for (coord, id, _) in
+                    blueprint.placed_at(&setup.resource_man, setup.camera.pointing_at)
+                {
+                    tile_tints.insert(coord, colors::LIGHT_BLUE.with_alpha(0.3));
+
+                    if let Some(tile_def) = setup.resource_man.registry.tiles.get(&id) {
+                        let pos = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+
+                        extra_instances.push((
+                            InstanceData::default()
+                                .with_alpha(0.6)
+                                .with_light_pos(camera_pos_float, None)
+                                .with_world_matrix(setup.camera.get_matrix().as_mat4())
+                                .with_model_matrix(Matrix4::from_translation(vec3(
+                                    pos.x,
+                                    pos.y,
+                                    FAR as Float,
+                                ))),
+                            setup.resource_man.get_model(tile_def.model),
+                        ));
+                    }
+                }
+            }
+        }
+
         error::error_popup(setup, gui);
 
         if !matches!(result, Ok(true)) {
@@ -447,7 +599,9 @@ fn render(
 async fn on_link_tile(
     resource_man: Arc<ResourceManager>,
     audio_man: &mut AudioManager,
+    game: &ActorRef<GameMsg>,
     pointing_cache: Arc<Mutex<Option<(Id, ActorRef<TileEntityMsg>)>>>,
+    pointing_at: TileCoord,
     linking_tile: TileCoord,
 ) {
     let Some((tile, entity)) = pointing_cache.lock().await.clone() else {
@@ -475,25 +629,21 @@ async fn on_link_tile(
             return;
         };
 
-        if old.is_some() {
-            entity
-                .send_message(TileEntityMsg::RemoveData(
-                    resource_man.registry.data_ids.link,
-                ))
-                .unwrap();
-
-            audio_man.play(resource_man.audio["click"].clone()).unwrap();
-            // TODO click2
+        let new = if old.is_some() {
+            None
         } else {
-            entity
-                .send_message(TileEntityMsg::SetDataValue(
-                    resource_man.registry.data_ids.link,
-                    Data::Coord(linking_tile),
-                ))
-                .unwrap();
+            Some(Data::Coord(linking_tile))
+        };
 
-            audio_man.play(resource_man.audio["click"].clone()).unwrap();
-        }
+        game.send_message(GameMsg::SetTileData {
+            coord: pointing_at,
+            values: vec![(resource_man.registry.data_ids.link, new)],
+            record: true,
+        })
+        .unwrap();
+
+        audio_man.play(resource_man.audio["click"].clone()).unwrap();
+        // TODO click2 for unlinking
     }
 }
 
@@ -570,6 +720,7 @@ pub fn on_event(
             // one by one
             if loop_store.selected_tile_id.take().is_none()
                 && loop_store.linking_tile.take().is_none()
+                && !mem::take(&mut loop_store.pasting)
             {
                 if loop_store
                     .gui_state
@@ -578,6 +729,9 @@ pub fn on_event(
                     runtime
                         .block_on(setup.game.call(GameMsg::SaveMap, None))?
                         .unwrap();
+
+                    renderer.thumbnail_request =
+                        loop_store.map_info.as_ref().map(|(_, name)| name.clone());
                 } else {
                     loop_store
                         .gui_state
@@ -590,7 +744,59 @@ pub fn on_event(
             }
         }
 
-        if setup.input_handler.main_pressed
+        if loop_store.pasting && setup.input_handler.main_pressed {
+            if let Some(blueprint) = &loop_store.blueprint {
+                let response = runtime
+                    .block_on(setup.game.call(
+                        |reply| {
+                            GameMsg::PlaceBlueprint(
+                                blueprint.placed_at(&setup.resource_man, setup.camera.pointing_at),
+                                true,
+                                Some(reply),
+                            )
+                        },
+                        None,
+                    ))?
+                    .unwrap();
+
+                let err_ids = &resource_man.registry.err_ids;
+
+                let error = match response {
+                    PlaceBlueprintResponse::Placed => {
+                        setup
+                            .audio_man
+                            .play(resource_man.audio["tile_placement"].clone())?;
+
+                        None
+                    }
+                    PlaceBlueprintResponse::UnknownTiles(ids) => Some((
+                        err_ids.unknown_blueprint_tiles,
+                        ids.iter()
+                            .map(|id| resource_man.interner.resolve(*id).unwrap_or("").to_string())
+                            .collect::<Vec<_>>(),
+                    )),
+                    PlaceBlueprintResponse::Locked(ids) => Some((
+                        err_ids.locked_blueprint_tiles,
+                        ids.iter()
+                            .map(|id| resource_man.tile_name(id).to_string())
+                            .collect(),
+                    )),
+                    PlaceBlueprintResponse::MissingItems(items) => Some((
+                        err_ids.missing_blueprint_items,
+                        items
+                            .iter()
+                            .map(|(id, amount)| format!("{} x{amount}", resource_man.item_name(id)))
+                            .collect(),
+                    )),
+                };
+
+                if let Some((err_id, names)) = error {
+                    resource_man
+                        .error_man
+                        .push((err_id, vec![names.join(", ")]), &resource_man);
+                }
+            }
+        } else if setup.input_handler.main_pressed
             || (setup.input_handler.shift_held && setup.input_handler.main_held)
         {
             if let Some(id) = loop_store.selected_tile_id {
@@ -652,7 +858,9 @@ pub fn on_event(
                 runtime.block_on(on_link_tile(
                     setup.resource_man.clone(),
                     &mut setup.audio_man,
+                    &setup.game,
                     loop_store.pointing_cache.clone(),
+                    setup.camera.pointing_at,
                     linking_tile,
                 ));
             } else if loop_store.config_open_at == Some(setup.camera.pointing_at) {
@@ -707,6 +915,105 @@ pub fn on_event(
             setup.game.send_message(GameMsg::Undo)?;
         }
 
+        if setup.input_handler.control_held && setup.input_handler.key_active(KeyActions::Redo) {
+            setup.game.send_message(GameMsg::Redo)?;
+        }
+
+        if setup.input_handler.control_held
+            && setup.input_handler.key_active(KeyActions::Copy)
+            && !loop_store.grouped_tiles.is_empty()
+        {
+            let coords = loop_store.grouped_tiles.iter().cloned().collect::<Vec<_>>();
+
+            let tiles = runtime
+                .block_on(
+                    setup
+                        .game
+                        .call(|reply| GameMsg::CopyTiles(coords, reply), None),
+                )?
+                .unwrap();
+
+            let blueprint = Blueprint::new(&setup.resource_man, tiles, setup.camera.pointing_at);
+
+            match blueprint.export(&setup.resource_man.interner) {
+                Ok(text) => {
+                    if let Err(err) = Clipboard::new().and_then(|mut v| v.set_text(text)) {
+                        log::warn!("Could not copy the blueprint to the clipboard: {err}");
+                    }
+                }
+                Err(err) => log::error!("Could not export the blueprint: {err}"),
+            }
+
+            loop_store.blueprint = Some(blueprint);
+            setup.audio_man.play(resource_man.audio["click"].clone())?;
+        }
+
+        if setup.input_handler.control_held && setup.input_handler.key_active(KeyActions::Paste) {
+            // blueprints shared as text take priority over the last copied one
+            if let Some(text) = Clipboard::new()
+                .and_then(|mut v| v.get_text())
+                .ok()
+                .filter(|v| v.trim_start().starts_with(BLUEPRINT_PREFIX))
+            {
+                match Blueprint::import(&setup.resource_man, &text) {
+                    Ok(blueprint) => loop_store.blueprint = Some(blueprint),
+                    Err(err) => {
+                        setup.resource_man.error_man.push(
+                            (
+                                setup.resource_man.registry.err_ids.invalid_blueprint,
+                                vec![err.to_string()],
+                            ),
+                            &setup.resource_man,
+                        );
+                    }
+                }
+            }
+
+            if loop_store.blueprint.is_some() {
+                loop_store.pasting = true;
+                loop_store.selected_tile_id = None;
+            }
+        }
+
+        let orientation = Orientation {
+            rotation: if !setup.input_handler.key_active(KeyActions::Rotate) {
+                0
+            } else if setup.input_handler.shift_held {
+                -1
+            } else {
+                1
+            },
+            mirrored: setup.input_handler.key_active(KeyActions::Mirror),
+        };
+
+        if orientation != Orientation::default() {
+            if loop_store.pasting {
+                if let Some(blueprint) = &mut loop_store.blueprint {
+                    blueprint.transform(&setup.resource_man, orientation);
+                }
+            } else if setup.input_handler.control_held
+                && loop_store.initial_cursor_position.is_none()
+                && !loop_store.grouped_tiles.is_empty()
+            {
+                let pivot = setup.camera.pointing_at;
+
+                setup.game.send_message(GameMsg::TransformTiles(
+                    loop_store.grouped_tiles.iter().cloned().collect::<Vec<_>>(),
+                    pivot,
+                    orientation,
+                    true,
+                ))?;
+
+                loop_store.grouped_tiles = loop_store
+                    .grouped_tiles
+                    .iter()
+                    .map(|coord| pivot + orientation.apply(*coord - pivot))
+                    .collect();
+
+                setup.audio_man.play(resource_man.audio["click"].clone())?;
+            }
+        }
+
         if setup.input_handler.key_active(KeyActions::Fullscreen) {
             setup.options.graphics.fullscreen = !setup.options.graphics.fullscreen;
             setup.options.synced = false
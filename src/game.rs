@@ -1,14 +1,14 @@
 use std::collections::VecDeque;
+use std::mem;
 use std::ops::Div;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use arraydeque::{ArrayDeque, Wrapping};
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use ractor::rpc::CallResult;
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort, SupervisionEvent};
-use rayon::prelude::*;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
 use automancy_defs::coord::TileCoord;
 use automancy_defs::hexx::HexBounds;
@@ -16,14 +16,18 @@ use automancy_defs::id::Id;
 use automancy_defs::log;
 use automancy_defs::math::{Float, Matrix4, FAR, HEX_GRID_LAYOUT};
 use automancy_defs::rendering::InstanceData;
-use automancy_resources::data::stack::ItemStack;
+use automancy_resources::data::stack::{ItemAmount, ItemStack};
 use automancy_resources::data::{Data, DataMap};
-use automancy_resources::ResourceManager;
+use automancy_resources::types::function::WorldSnapshot;
+use automancy_resources::{ResourceManager, RESOURCE_MAN};
 
+use crate::blueprint::{Blueprint, BlueprintTiles, Orientation};
 use crate::event::EventLoopStorage;
 use crate::game::GameMsg::*;
-use crate::map::{Map, MapInfo, TileEntities};
+use crate::map::{chunk_of, is_creative, Map, MapInfo, MapSeed, TileEntities, MAIN_MENU};
+use crate::remap::IdRemap;
 use crate::setup::GameSetup;
+use crate::statistics::{StatisticKind, StatisticWindow, Statistics, StatisticsReport};
 use crate::tile_entity::{TileEntity, TileEntityMsg};
 use crate::util::actor::multi_call_iter;
 
@@ -35,7 +39,10 @@ pub const MAX_ALLOWED_TICK_INTERVAL: Duration = TICK_INTERVAL.saturating_mul(5);
 pub const TRANSACTION_ANIMATION_SPEED: Duration = Duration::from_nanos(666_666_666);
 pub const TAKE_ITEM_ANIMATION_SPEED: Duration = Duration::from_nanos(200_000_000);
 
-pub type TickUnit = u16;
+/// The maximum amount of times messages get passed on between tiles within one tick.
+pub const MAX_TRANSACTION_WAVES: usize = 64;
+
+pub type TickUnit = u64;
 
 #[derive(Debug, Clone, Copy)]
 pub struct TransactionRecord {
@@ -57,28 +64,104 @@ pub type TransactionRecords =
 
 #[derive(Debug)]
 pub struct GameState {
-    /// a count of all the ticks that have happened
+    /// the resources the game runs with, which are swapped out when they are reloaded
+    resource_man: Arc<ResourceManager>,
+
+    /// a count of all the ticks that have happened on the map, which is saved along with it
     tick_count: TickUnit,
     /// is the game stopped
     stopped: bool,
 
     /// the tile entities
     tile_entities: TileEntities,
+    /// the messages between tiles that are delivered in the next tick
+    leftover_messages: Vec<GameMsg>,
+    /// the tiles and their data as of the last snapshot
+    world: Arc<WorldSnapshot>,
+    /// the tiles whose data changed since the last snapshot
+    changed_data: HashSet<TileCoord>,
     /// the map
     map: Map,
 
-    /// what to do to undo the last UNDO_CACHE_SIZE user events
-    undo_steps: ArrayDeque<Vec<GameMsg>, 16, Wrapping>,
+    /// what to do to undo and redo the user's changes
+    history: History,
     /// records transactions to be drawn
     transaction_records: TransactionRecords,
+    /// the item throughput of the tiles
+    statistics: Statistics,
+
+    /// how often the map is autosaved, or zero to never autosave
+    autosave_interval: Duration,
+    /// when the map was last autosaved
+    last_autosave: Instant,
+    /// the autosave being written, if one was started
+    autosave: Option<JoinHandle<()>>,
+}
+
+/// The user's changes to the map that can be undone and redone.
+///
+/// Each step is the list of messages that reverses one change. The messages are sent with recording on,
+/// so that undoing a step records the step that redoes it, and the other way around.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: VecDeque<Vec<GameMsg>>,
+    redo: Vec<Vec<GameMsg>>,
+    /// the most steps that are kept, or 0 to keep every step
+    depth: usize,
+    /// the reversing steps recorded while undoing or redoing
+    replaying: Option<Vec<GameMsg>>,
+}
+
+impl History {
+    /// Records the step that reverses a change. A new change clears the steps to redo.
+    fn record(&mut self, mut step: Vec<GameMsg>) {
+        if step.is_empty() {
+            return;
+        }
+
+        if let Some(replaying) = &mut self.replaying {
+            // the changes are reversed in the opposite order they were replayed in
+            step.append(replaying);
+            *replaying = step;
+
+            return;
+        }
+
+        self.redo.clear();
+        self.push_undo(step);
+    }
+
+    fn push_undo(&mut self, step: Vec<GameMsg>) {
+        if step.is_empty() {
+            return;
+        }
+
+        self.undo.push_back(step);
+        self.trim();
+    }
+
+    /// Drops the oldest steps to undo that are over the depth.
+    fn trim(&mut self) {
+        if self.depth > 0 {
+            while self.undo.len() > self.depth {
+                self.undo.pop_front();
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
 }
 
 pub async fn load_map(
     setup: &GameSetup,
     loop_store: &mut EventLoopStorage,
     map_name: String,
+    remap: IdRemap,
 ) -> anyhow::Result<()> {
-    setup.game.send_message(LoadMap(map_name))?;
+    setup.game.send_message(LoadMap(map_name, remap))?;
     loop_store.map_info = Some(
         setup
             .game
@@ -91,6 +174,19 @@ pub async fn load_map(
     Ok(())
 }
 
+/// Tells the player that the map could not be saved.
+fn report_unwritable_map(resource_man: &ResourceManager, map_name: &str, e: anyhow::Error) {
+    log::error!("Could not save map {map_name}: {e:?}");
+
+    resource_man.error_man.push(
+        (
+            resource_man.registry.err_ids.unwritable_map,
+            vec![map_name.to_string(), e.to_string()],
+        ),
+        resource_man,
+    );
+}
+
 /// Represents a message the game receives
 #[derive(Debug)]
 pub enum GameMsg {
@@ -108,14 +204,41 @@ pub enum GameMsg {
         reply: Option<RpcReplyPort<PlaceTileResponse>>,
     },
     MoveTiles(Vec<TileCoord>, TileCoord, bool),
+    /// rotate and mirror the tiles around the pivot
+    TransformTiles(Vec<TileCoord>, TileCoord, Orientation, bool),
+    /// place all the tiles at once as a single undo step, or none of them if any cannot be placed
+    PlaceBlueprint(
+        BlueprintTiles,
+        bool,
+        Option<RpcReplyPort<PlaceBlueprintResponse>>,
+    ),
+    /// get the tiles at the given positions, along with their data
+    CopyTiles(Vec<TileCoord>, RpcReplyPort<BlueprintTiles>),
+    /// set the data values of a tile, removing the ones that are None
+    SetTileData {
+        coord: TileCoord,
+        values: Vec<(Id, Option<Data>)>,
+        record: bool,
+    },
     Undo,
-
-    /// load a map
-    LoadMap(String),
+    Redo,
+    /// record the step that undoes a change made without being recorded
+    RecordHistory(Vec<GameMsg>),
+    /// set the most undo steps that are kept, or 0 to keep every step
+    SetUndoDepth(usize),
+    /// set how often the map is autosaved into its backups, or zero to never autosave
+    SetAutosaveInterval(Duration),
+
+    /// load a map, replacing the IDs that are missing from the game
+    LoadMap(String, IdRemap),
+    /// swap in newly loaded resources, moving the map and its tiles over to them
+    ReloadResources(Arc<ResourceManager>, RpcReplyPort<()>),
     /// save the map
     SaveMap(RpcReplyPort<()>),
 
     GetMapInfoAndName(RpcReplyPort<(Arc<Mutex<MapInfo>>, String)>),
+    /// get how many tiles the map has
+    GetTileCount(RpcReplyPort<usize>),
 
     /// get the tile at the given position
     GetTile(TileCoord, RpcReplyPort<Option<Id>>),
@@ -130,6 +253,13 @@ pub enum GameMsg {
 
     GetRecordedTransactions(RpcReplyPort<TransactionRecords>),
     RecordTransaction(ItemStack, TileCoord, TileCoord),
+
+    /// get the item throughput statistics over the given window
+    GetStatistics(StatisticWindow, RpcReplyPort<StatisticsReport>),
+    /// record items going through the tile at the given position
+    RecordStatistic(TileCoord, ItemStack, StatisticKind),
+    /// the data of the tile at the given position changed, so the next snapshot fetches it again
+    TileDataChanged(TileCoord),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -139,22 +269,54 @@ pub enum PlaceTileResponse {
     Ignored,
 }
 
-pub struct Game {
-    pub resource_man: Arc<ResourceManager>,
+/// What happened to a blueprint. Unless every tile was placed, none were.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaceBlueprintResponse {
+    Placed,
+    /// the tiles that do not exist in the game
+    UnknownTiles(Vec<Id>),
+    /// the tiles that are locked behind a research
+    Locked(Vec<Id>),
+    /// the category items the player is missing, and how many more of each are needed
+    MissingItems(Vec<(Id, ItemAmount)>),
+}
+
+pub struct Game;
+
+impl Game {
+    /// Handles every message of an undo or redo step, and returns the step that reverses it.
+    async fn replay(
+        &self,
+        myself: ActorRef<GameMsg>,
+        state: &mut GameState,
+        step: Vec<GameMsg>,
+    ) -> Result<Vec<GameMsg>, ActorProcessingErr> {
+        state.history.replaying = Some(vec![]);
+
+        for msg in step {
+            if let Err(err) = self.handle(myself.clone(), msg, state).await {
+                state.history.replaying = None;
+
+                return Err(err);
+            }
+        }
+
+        Ok(state.history.replaying.take().unwrap_or_default())
+    }
 }
 
 #[async_trait::async_trait]
 impl Actor for Game {
     type Msg = GameMsg;
     type State = GameState;
-    type Arguments = ();
+    type Arguments = Arc<ResourceManager>;
 
     async fn pre_start(
         &self,
         _myself: ActorRef<Self::Msg>,
-        _args: Self::Arguments,
+        resource_man: Self::Arguments,
     ) -> Result<Self::State, ActorProcessingErr> {
-        Ok(Self::State::default())
+        Ok(Self::State::new(resource_man))
     }
 
     async fn handle(
@@ -163,29 +325,69 @@ impl Actor for Game {
         message: Self::Msg,
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
+        let resource_man = state.resource_man.clone();
+
         match message {
-            LoadMap(name) => {
+            LoadMap(name, remap) => {
                 for tile_entity in state.tile_entities.values() {
                     tile_entity.stop(Some("Loading new map".to_string()));
                 }
 
                 let (map, tile_entities) =
-                    Map::load(myself.clone(), self.resource_man.clone(), &name).await;
+                    Map::load(myself.clone(), resource_man.clone(), &name, &remap).await;
 
+                state.tick_count = map.info.lock().await.tick_count;
                 state.map = map;
                 state.tile_entities = tile_entities;
+                state.leftover_messages.clear();
+                state.world = Default::default();
+                state.changed_data = state.tile_entities.keys().cloned().collect();
                 state.transaction_records.clear();
-                state.undo_steps.clear();
+                state.history.clear();
+                state.statistics = Default::default();
+                state.last_autosave = Instant::now();
 
                 log::info!("Successfully loaded map {name}!");
                 return Ok(());
             }
-            SaveMap(reply) => {
-                state
+            ReloadResources(new_resource_man, reply) => {
+                let tile_entities = mem::take(&mut state.tile_entities);
+
+                state.tile_entities = state
                     .map
-                    .save(&self.resource_man.interner, &state.tile_entities)
+                    .reload(
+                        myself.clone(),
+                        &resource_man,
+                        new_resource_man.clone(),
+                        tile_entities,
+                    )
                     .await;
-                log::info!("Saved map {}", state.map.map_name.clone());
+
+                // functions look things up in the global one, and no tile runs until the next tick
+                RESOURCE_MAN
+                    .write()
+                    .unwrap()
+                    .replace(new_resource_man.clone());
+                state.resource_man = new_resource_man;
+
+                // these refer to tiles and items by the old IDs
+                state.leftover_messages.clear();
+                state.world = Default::default();
+                state.changed_data = state.tile_entities.keys().cloned().collect();
+                state.transaction_records.clear();
+                state.history.clear();
+                state.statistics = Default::default();
+
+                log::info!("Reloaded resources for map {}", state.map.map_name);
+                reply.send(()).unwrap();
+
+                return Ok(());
+            }
+            SaveMap(reply) => {
+                match state.map.save(&resource_man, &state.tile_entities).await {
+                    Ok(()) => log::info!("Saved map {}", state.map.map_name.clone()),
+                    Err(e) => report_unwritable_map(&resource_man, &state.map.map_name, e),
+                }
                 reply.send(()).unwrap();
             }
             GetMapInfoAndName(reply) => {
@@ -195,6 +397,11 @@ impl Actor for Game {
 
                 return Ok(());
             }
+            GetTileCount(reply) => {
+                reply.send(state.map.tiles.len()).unwrap();
+
+                return Ok(());
+            }
             rest => {
                 if state.stopped {
                     return Ok(());
@@ -202,7 +409,48 @@ impl Actor for Game {
 
                 match rest {
                     Tick => {
-                        tick(state);
+                        tick(state).await;
+
+                        {
+                            let mut info = state.map.info.lock().await;
+
+                            info.tick_count = state.tick_count;
+                            if state.map.map_name != MAIN_MENU {
+                                info.play_time += TICK_INTERVAL;
+                            }
+                        }
+
+                        if !state.autosave_interval.is_zero()
+                            && state.last_autosave.elapsed() >= state.autosave_interval
+                            && state.map.map_name != MAIN_MENU
+                            && state.autosave.as_ref().is_none_or(JoinHandle::is_finished)
+                        {
+                            state.last_autosave = Instant::now();
+
+                            match state
+                                .map
+                                .snapshot_backup(&resource_man, &state.tile_entities)
+                                .await
+                            {
+                                Ok(snapshot) => {
+                                    let resource_man = resource_man.clone();
+                                    let map_name = state.map.map_name.clone();
+
+                                    // the backup is encoded and written off the tick, so that the ticks keep their pace
+                                    state.autosave = Some(tokio::task::spawn_blocking(move || {
+                                        match snapshot.write_backup() {
+                                            Ok(()) => log::info!("Autosaved map {map_name}"),
+                                            Err(e) => {
+                                                report_unwritable_map(&resource_man, &map_name, e)
+                                            }
+                                        }
+                                    }));
+                                }
+                                Err(e) => {
+                                    report_unwritable_map(&resource_man, &state.map.map_name, e)
+                                }
+                            }
+                        }
                     }
                     RenderInfoRequest {
                         culling_range,
@@ -214,11 +462,11 @@ impl Actor for Game {
                             .iter()
                             .filter(|(coord, _)| culling_range.is_in_bounds(***coord))
                             .flat_map(|(coord, id)| {
-                                self.resource_man
+                                resource_man
                                     .registry
                                     .tiles
                                     .get(id)
-                                    .map(|tile| self.resource_man.get_model(tile.model))
+                                    .map(|tile| resource_man.get_model(tile.model))
                                     .map(|model| {
                                         let p = HEX_GRID_LAYOUT.hex_to_world_pos((*coord).into());
 
@@ -258,83 +506,74 @@ impl Actor for Game {
                         record,
                         reply,
                     } => {
-                        if let Some(old_id) = state.map.tiles.get(&coord) {
-                            if *old_id == id {
-                                if let Some(reply) = reply {
-                                    reply.send(PlaceTileResponse::Ignored).unwrap();
-                                }
+                        let (response, old_tile) = place_tile(
+                            resource_man.clone(),
+                            myself.clone(),
+                            state,
+                            coord,
+                            id,
+                            data,
+                        )
+                        .await;
 
-                                return Ok(());
-                            }
+                        if let Some(reply) = reply {
+                            reply.send(response).unwrap();
                         }
 
-                        let mut skip = false;
+                        if record && !matches!(response, PlaceTileResponse::Ignored) {
+                            let (id, data) = old_tile.unwrap_or((resource_man.registry.none, None));
 
+                            state.history.record(vec![PlaceTile {
+                                coord,
+                                id,
+                                record: true,
+                                reply: None,
+                                data,
+                            }]);
+                        }
+                    }
+                    PlaceBlueprint(tiles, record, reply) => {
+                        let response = match place_blueprint(
+                            resource_man.clone(),
+                            myself.clone(),
+                            state,
+                            tiles,
+                        )
+                        .await
                         {
-                            let lock = &mut state.map.info.lock().await;
-
-                            try_category(&self.resource_man, id, |item| {
-                                if let Data::Inventory(inventory) = lock
-                                    .data
-                                    .entry(self.resource_man.registry.data_ids.player_inventory)
-                                    .or_insert_with(|| Data::Inventory(Default::default()))
-                                {
-                                    if inventory.get(item) < 1 {
-                                        skip = true
-                                    }
+                            Ok(undo) => {
+                                if record && !undo.is_empty() {
+                                    state.history.record(undo);
                                 }
-                            });
-                        }
 
-                        if skip {
-                            if let Some(reply) = reply {
-                                reply.send(PlaceTileResponse::Ignored).unwrap();
+                                PlaceBlueprintResponse::Placed
                             }
-                            return Ok(());
-                        }
+                            Err(response) => response,
+                        };
 
-                        let old_tile = if id == self.resource_man.registry.none {
-                            if !state.map.tiles.contains_key(&coord) {
-                                if let Some(reply) = reply {
-                                    reply.send(PlaceTileResponse::Ignored).unwrap();
+                        if let Some(reply) = reply {
+                            reply.send(response).unwrap();
+                        }
+                    }
+                    CopyTiles(coords, reply) => {
+                        let mut tiles = vec![];
+
+                        for coord in coords {
+                            if let Some((id, tile_entity)) = state
+                                .map
+                                .tiles
+                                .get(&coord)
+                                .zip(state.tile_entities.get(&coord))
+                            {
+                                if let Ok(CallResult::Success(data)) =
+                                    tile_entity.call(TileEntityMsg::GetData, None).await
+                                {
+                                    tiles.push((coord, *id, data));
                                 }
-
-                                return Ok(());
-                            }
-
-                            if let Some(reply) = reply {
-                                reply.send(PlaceTileResponse::Removed).unwrap();
                             }
-
-                            remove_tile(&self.resource_man, state, coord).await
-                        } else {
-                            if let Some(reply) = reply {
-                                reply.send(PlaceTileResponse::Placed).unwrap();
-                            }
-
-                            insert_new_tile(
-                                self.resource_man.clone(),
-                                myself.clone(),
-                                state,
-                                coord,
-                                id,
-                                data,
-                            )
-                            .await
-                        };
-
-                        if record {
-                            let (id, data) =
-                                old_tile.unwrap_or((self.resource_man.registry.none, None));
-
-                            state.undo_steps.push_back(vec![PlaceTile {
-                                coord,
-                                id,
-                                record: false,
-                                reply: None,
-                                data,
-                            }]);
                         }
+
+                        reply.send(tiles).unwrap();
                     }
                     GetTile(coord, reply) => {
                         reply.send(state.map.tiles.get(&coord).cloned()).unwrap();
@@ -347,18 +586,88 @@ impl Actor for Game {
                     ForwardMsgToTile(coord, msg) => {
                         if let Some(tile_entity) = state.tile_entities.get(&coord) {
                             tile_entity.send_message(msg).unwrap();
+                            data_changed(state, coord);
                         }
                     }
                     StopTicking => {
                         state.stopped = true;
                     }
                     Undo => {
-                        if let Some(step) = state.undo_steps.pop_back() {
-                            for msg in step {
-                                myself.send_message(msg).unwrap();
+                        if let Some(step) = state.history.undo.pop_back() {
+                            let redo = self.replay(myself.clone(), state, step).await?;
+
+                            if redo.is_empty() {
+                                log::warn!("Undoing changed nothing, so there is nothing to redo");
+                            } else {
+                                state.history.redo.push(redo);
                             }
                         }
                     }
+                    Redo => {
+                        if let Some(step) = state.history.redo.pop() {
+                            let undo = self.replay(myself.clone(), state, step).await?;
+
+                            state.history.push_undo(undo);
+                        }
+                    }
+                    RecordHistory(step) => {
+                        state.history.record(step);
+                    }
+                    SetUndoDepth(depth) => {
+                        state.history.depth = depth;
+                        state.history.trim();
+                    }
+                    SetAutosaveInterval(interval) => {
+                        state.autosave_interval = interval;
+                    }
+                    SetTileData {
+                        coord,
+                        values,
+                        record,
+                    } => {
+                        let Some(tile_entity) = state.tile_entities.get(&coord).cloned() else {
+                            return Ok(());
+                        };
+                        data_changed(state, coord);
+
+                        let mut undo = vec![];
+
+                        for (key, value) in values {
+                            // the tile may have stopped, in which case there is nothing to change
+                            let Ok(CallResult::Success(old)) = tile_entity
+                                .call(|reply| TileEntityMsg::GetDataValue(key, reply), None)
+                                .await
+                            else {
+                                continue;
+                            };
+
+                            if old == value {
+                                continue;
+                            }
+
+                            let sent = match value {
+                                Some(value) => tile_entity
+                                    .send_message(TileEntityMsg::SetDataValue(key, value)),
+                                None => tile_entity.send_message(TileEntityMsg::RemoveData(key)),
+                            };
+
+                            if sent.is_err() {
+                                continue;
+                            }
+
+                            undo.push((key, old));
+                        }
+
+                        if record && !undo.is_empty() {
+                            undo.reverse();
+
+                            state.history.record(vec![SetTileData {
+                                coord,
+                                values: undo,
+                                record: true,
+                            }]);
+                        }
+                    }
                     GetRecordedTransactions(reply) => {
                         let mut to_remove = HashMap::new();
 
@@ -388,46 +697,27 @@ impl Actor for Game {
                         reply.send(state.transaction_records.clone()).unwrap();
                     }
                     RecordTransaction(stack, source_coord, coord) => {
-                        if let Some((instant, _)) = state
-                            .transaction_records
-                            .get(&(source_coord, coord))
-                            .and_then(|v| v.back())
-                        {
-                            if Instant::now().duration_since(*instant)
-                                < TRANSACTION_ANIMATION_SPEED.div(4)
-                            {
-                                return Ok(());
-                            }
-                        }
-
-                        if let Some((source_id, id)) = state
-                            .map
-                            .tiles
-                            .get(&source_coord)
-                            .cloned()
-                            .zip(state.map.tiles.get(&coord).cloned())
-                        {
-                            state
-                                .transaction_records
-                                .entry((source_coord, coord))
-                                .or_insert_with(Default::default)
-                                .push_back((
-                                    Instant::now(),
-                                    TransactionRecord {
-                                        stack,
-                                        source_id,
-                                        id,
-                                    },
-                                ));
-                        }
+                        record_transaction(state, stack, source_coord, coord);
+                    }
+                    GetStatistics(window, reply) => {
+                        reply
+                            .send(state.statistics.report(window, &state.map.tiles))
+                            .unwrap();
+                    }
+                    RecordStatistic(coord, stack, kind) => {
+                        state.statistics.record(coord, stack, kind);
+                    }
+                    TileDataChanged(coord) => {
+                        data_changed(state, coord);
                     }
                     MoveTiles(tiles, direction, record) => {
-                        let mut undo = vec![];
+                        let mut moved = vec![];
+                        let mut overwritten = vec![];
 
                         let mut removed = Vec::new();
 
                         for coord in tiles {
-                            if let Some(old) = remove_tile(&self.resource_man, state, coord).await {
+                            if let Some(old) = remove_tile(&resource_man, state, coord).await {
                                 removed.push((coord, old));
                             }
                         }
@@ -435,23 +725,80 @@ impl Actor for Game {
                         for (coord, (id, data)) in removed {
                             let new_coord = coord + direction;
 
-                            insert_new_tile(
-                                self.resource_man.clone(),
+                            if let Some((old_id, old_data)) = insert_new_tile(
+                                resource_man.clone(),
                                 myself.clone(),
                                 state,
                                 new_coord,
                                 id,
                                 data,
                             )
-                            .await;
+                            .await
+                            {
+                                overwritten.push(PlaceTile {
+                                    coord: new_coord,
+                                    id: old_id,
+                                    data: old_data,
+                                    record: true,
+                                    reply: None,
+                                });
+                            }
+
+                            moved.push(new_coord);
+                        }
+
+                        if record && !moved.is_empty() {
+                            let mut undo = vec![MoveTiles(moved, -direction, true)];
+                            undo.append(&mut overwritten);
+
+                            state.history.record(undo);
+                        }
+                    }
+                    TransformTiles(tiles, pivot, orientation, record) => {
+                        let mut removed = Vec::new();
+
+                        for coord in tiles {
+                            if let Some((id, data)) = remove_tile(&resource_man, state, coord).await
+                            {
+                                removed.push((coord, id, data.unwrap_or_default()));
+                            }
+                        }
+
+                        let mut blueprint = Blueprint::new(&resource_man, removed, pivot);
+                        blueprint.transform(&resource_man, orientation);
+
+                        let mut moved = vec![];
+                        let mut overwritten = vec![];
+
+                        for (coord, id, data) in blueprint.placed_at(&resource_man, pivot) {
+                            if let Some((old_id, old_data)) = insert_new_tile(
+                                resource_man.clone(),
+                                myself.clone(),
+                                state,
+                                coord,
+                                id,
+                                Some(data),
+                            )
+                            .await
+                            {
+                                overwritten.push(PlaceTile {
+                                    coord,
+                                    id: old_id,
+                                    data: old_data,
+                                    record: true,
+                                    reply: None,
+                                });
+                            }
 
-                            undo.push(new_coord);
+                            moved.push(coord);
                         }
 
-                        if record {
-                            state
-                                .undo_steps
-                                .push_back(vec![MoveTiles(undo, -direction, false)]);
+                        if record && !moved.is_empty() {
+                            let mut undo =
+                                vec![TransformTiles(moved, pivot, orientation.inverse(), true)];
+                            undo.append(&mut overwritten);
+
+                            state.history.record(undo);
                         }
                     }
                     _ => {}
@@ -512,15 +859,17 @@ pub async fn new_tile(
     game: ActorRef<GameMsg>,
     coord: TileCoord,
     id: Id,
+    seed: MapSeed,
 ) -> ActorRef<TileEntityMsg> {
     let (actor, _handle) = Actor::spawn_linked(
         None,
         TileEntity {
             id,
             coord,
+            seed,
             resource_man,
         },
-        (game.clone(),),
+        (),
         game.get_cell(),
     )
     .await
@@ -529,6 +878,204 @@ pub async fn new_tile(
     actor
 }
 
+/// Places a tile at the given position, or removes the tile there if the ID is none.
+/// Nothing happens if the tile is already there, is locked behind a research, or the player is missing its category item
+/// outside of creative mode.
+/// Returns what happened, and the tile that was there before.
+async fn place_tile(
+    resource_man: Arc<ResourceManager>,
+    game: ActorRef<GameMsg>,
+    state: &mut GameState,
+    coord: TileCoord,
+    id: Id,
+    mut data: Option<DataMap>,
+) -> (PlaceTileResponse, Option<(Id, Option<DataMap>)>) {
+    if state.map.tiles.get(&coord) == Some(&id) {
+        return (PlaceTileResponse::Ignored, None);
+    }
+
+    let mut skip = false;
+
+    {
+        let lock = &mut state.map.info.lock().await;
+
+        if !resource_man.is_unlocked(id, &lock.data) {
+            skip = true;
+        }
+
+        if let Some(data) = &mut data {
+            remove_locked_script(&resource_man, &lock.data, data);
+        }
+
+        if !is_creative(&resource_man, &lock.data) {
+            try_category(&resource_man, id, |item| {
+                if let Data::Inventory(inventory) = lock
+                    .data
+                    .entry(resource_man.registry.data_ids.player_inventory)
+                    .or_insert_with(|| Data::Inventory(Default::default()))
+                {
+                    if inventory.get(item) < 1 {
+                        skip = true
+                    }
+                }
+            });
+        }
+    }
+
+    if skip {
+        return (PlaceTileResponse::Ignored, None);
+    }
+
+    if id == resource_man.registry.none {
+        if !state.map.tiles.contains_key(&coord) {
+            return (PlaceTileResponse::Ignored, None);
+        }
+
+        let old_tile = remove_tile(&resource_man, state, coord).await;
+
+        (PlaceTileResponse::Removed, old_tile)
+    } else {
+        let old_tile = insert_new_tile(resource_man, game, state, coord, id, data).await;
+
+        (PlaceTileResponse::Placed, old_tile)
+    }
+}
+
+/// Removes the script from the tile data if it is locked behind a research.
+fn remove_locked_script(resource_man: &ResourceManager, game_data: &DataMap, data: &mut DataMap) {
+    let script = resource_man.registry.data_ids.script;
+
+    if let Some(Data::Id(id)) = data.get(&script) {
+        if !resource_man.is_unlocked(*id, game_data) {
+            data.remove(&script);
+        }
+    }
+}
+
+/// Places every tile of a blueprint, or none of them if any tile does not exist, is locked behind a research,
+/// or the player is missing the category items for all of them outside of creative mode.
+/// Returns the step that undoes the placement.
+async fn place_blueprint(
+    resource_man: Arc<ResourceManager>,
+    game: ActorRef<GameMsg>,
+    state: &mut GameState,
+    tiles: BlueprintTiles,
+) -> Result<Vec<GameMsg>, PlaceBlueprintResponse> {
+    let none = resource_man.registry.none;
+
+    let mut tiles = tiles
+        .into_iter()
+        .filter(|(coord, id, _)| match state.map.tiles.get(coord) {
+            Some(old) => old != id,
+            None => *id != none,
+        })
+        .collect::<Vec<_>>();
+
+    let unknown = tiles
+        .iter()
+        .map(|(_, id, _)| *id)
+        .filter(|id| !resource_man.registry.tiles.contains_key(id))
+        .collect::<HashSet<_>>();
+
+    if !unknown.is_empty() {
+        return Err(PlaceBlueprintResponse::UnknownTiles(
+            unknown.into_iter().collect(),
+        ));
+    }
+
+    {
+        let lock = &mut state.map.info.lock().await;
+
+        let locked = tiles
+            .iter()
+            .map(|(_, id, _)| *id)
+            .filter(|id| !resource_man.is_unlocked(*id, &lock.data))
+            .collect::<HashSet<_>>();
+
+        if !locked.is_empty() {
+            return Err(PlaceBlueprintResponse::Locked(locked.into_iter().collect()));
+        }
+
+        for (_, _, data) in &mut tiles {
+            remove_locked_script(&resource_man, &lock.data, data);
+        }
+
+        if !is_creative(&resource_man, &lock.data) {
+            // the tiles being replaced are given back before the new ones are taken
+            let mut needed = HashMap::<Id, ItemAmount>::new();
+
+            for (coord, id, _) in &tiles {
+                try_category(&resource_man, *id, |item| {
+                    *needed.entry(item).or_insert(0) += 1;
+                });
+
+                if let Some(old) = state.map.tiles.get(coord) {
+                    try_category(&resource_man, *old, |item| {
+                        *needed.entry(item).or_insert(0) -= 1;
+                    });
+                }
+            }
+
+            let mut inventory = match lock
+                .data
+                .get(&resource_man.registry.data_ids.player_inventory)
+            {
+                Some(Data::Inventory(inventory)) => inventory.clone(),
+                _ => Default::default(),
+            };
+
+            let missing = needed
+                .into_iter()
+                .filter_map(|(item, amount)| {
+                    let stored = inventory.get(item);
+
+                    (amount > stored).then_some((item, amount - stored))
+                })
+                .collect::<Vec<_>>();
+
+            if !missing.is_empty() {
+                return Err(PlaceBlueprintResponse::MissingItems(missing));
+            }
+        }
+    }
+
+    let mut old_tiles = HashMap::new();
+
+    for (coord, _, _) in &tiles {
+        if let Some(old) = remove_tile(&resource_man, state, *coord).await {
+            old_tiles.insert(*coord, old);
+        }
+    }
+
+    let mut undo = vec![];
+
+    for (coord, id, data) in tiles {
+        if id != none {
+            insert_new_tile(
+                resource_man.clone(),
+                game.clone(),
+                state,
+                coord,
+                id,
+                Some(data),
+            )
+            .await;
+        }
+
+        let (id, data) = old_tiles.remove(&coord).unwrap_or((none, None));
+
+        undo.push(PlaceTile {
+            coord,
+            id,
+            record: true,
+            reply: None,
+            data,
+        });
+    }
+
+    Ok(undo)
+}
+
 /// Stops a tile and removes it from the game
 async fn remove_tile(
     resource_man: &ResourceManager,
@@ -544,15 +1091,18 @@ async fn remove_tile(
         {
             let lock = &mut state.map.info.lock().await;
 
-            try_category(resource_man, tile, |item| {
-                if let Data::Inventory(inventory) = lock
-                    .data
-                    .entry(resource_man.registry.data_ids.player_inventory)
-                    .or_insert_with(|| Data::Inventory(Default::default()))
-                {
-                    inventory.add(item, 1);
-                }
-            });
+            // creative maps never took the item, so there is nothing to give back
+            if !is_creative(resource_man, &lock.data) {
+                try_category(resource_man, tile, |item| {
+                    if let Data::Inventory(inventory) = lock
+                        .data
+                        .entry(resource_man.registry.data_ids.player_inventory)
+                        .or_insert_with(|| Data::Inventory(Default::default()))
+                    {
+                        inventory.add(item, 1);
+                    }
+                });
+            }
         }
 
         let data = tile_entity
@@ -562,6 +1112,7 @@ async fn remove_tile(
             .map(CallResult::unwrap);
 
         tile_entity.stop(Some("Removed from game".to_string()));
+        data_changed(state, coord);
 
         Some((tile, data))
     } else {
@@ -581,30 +1132,35 @@ async fn insert_new_tile(
     let old = remove_tile(&resource_man, state, coord).await;
 
     let mut skip = false;
+    let seed;
 
     {
         let lock = &mut state.map.info.lock().await;
 
-        try_category(&resource_man, tile, |item| {
-            if let Data::Inventory(inventory) = lock
-                .data
-                .entry(resource_man.registry.data_ids.player_inventory)
-                .or_insert_with(|| Data::Inventory(Default::default()))
-            {
-                if inventory.get(item) < 1 {
-                    skip = true;
-                }
+        seed = lock.seed(&resource_man);
 
-                inventory.take(item, 1);
-            }
-        });
+        if !is_creative(&resource_man, &lock.data) {
+            try_category(&resource_man, tile, |item| {
+                if let Data::Inventory(inventory) = lock
+                    .data
+                    .entry(resource_man.registry.data_ids.player_inventory)
+                    .or_insert_with(|| Data::Inventory(Default::default()))
+                {
+                    if inventory.get(item) < 1 {
+                        skip = true;
+                    }
+
+                    inventory.take(item, 1);
+                }
+            });
+        }
     }
 
     if skip {
         return None;
     }
 
-    let tile_entity = new_tile(resource_man, game, coord, tile).await;
+    let tile_entity = new_tile(resource_man, game, coord, tile, seed).await;
 
     if let Some(data) = data {
         tile_entity
@@ -614,26 +1170,258 @@ async fn insert_new_tile(
 
     state.tile_entities.insert(coord, tile_entity);
     state.map.tiles.insert(coord, tile);
+    data_changed(state, coord);
 
     old
 }
 
-fn inner_tick(state: &mut GameState) {
-    state.tile_entities.par_iter().for_each(|(_, tile_entity)| {
-        if let Err(e) = tile_entity.send_message(TileEntityMsg::Tick {
+/// Marks the tile at the given position as changed, so that the next snapshot fetches its data
+/// and the next save writes its chunk.
+fn data_changed(state: &mut GameState, coord: TileCoord) {
+    state.changed_data.insert(coord);
+    state.map.dirty_chunks.insert(chunk_of(coord));
+}
+
+/// Records a transaction to be drawn, unless the last one between the two tiles is too recent.
+fn record_transaction(
+    state: &mut GameState,
+    stack: ItemStack,
+    source_coord: TileCoord,
+    coord: TileCoord,
+) {
+    if let Some((instant, _)) = state
+        .transaction_records
+        .get(&(source_coord, coord))
+        .and_then(|v| v.back())
+    {
+        if Instant::now().duration_since(*instant) < TRANSACTION_ANIMATION_SPEED.div(4) {
+            return;
+        }
+    }
+
+    if let Some((source_id, id)) = state
+        .map
+        .tiles
+        .get(&source_coord)
+        .cloned()
+        .zip(state.map.tiles.get(&coord).cloned())
+    {
+        state
+            .transaction_records
+            .entry((source_coord, coord))
+            .or_insert_with(Default::default)
+            .push_back((
+                Instant::now(),
+                TransactionRecord {
+                    stack,
+                    source_id,
+                    id,
+                },
+            ));
+    }
+}
+
+/// Waits for the tile entities at the given coordinates to finish handling their messages, and takes what they have sent out.
+async fn take_outboxes(state: &GameState, coords: &[TileCoord]) -> Vec<GameMsg> {
+    let tile_entities = coords
+        .iter()
+        .flat_map(|coord| state.tile_entities.get(coord))
+        .collect::<Vec<_>>();
+
+    match multi_call_iter(
+        tile_entities.iter().cloned(),
+        tile_entities.len(),
+        TileEntityMsg::TakeOutbox,
+        None,
+    )
+    .await
+    {
+        Ok(results) => results
+            .into_iter()
+            .flat_map(|result| match result {
+                CallResult::Success(outbox) => outbox,
+                _ => vec![],
+            })
+            .collect(),
+        Err(e) => {
+            log::error!("{e:?}");
+
+            vec![]
+        }
+    }
+}
+
+/// Takes the tiles of the map along with their data, for the functions of the tiles to query during the tick.
+///
+/// The snapshot is updated in place with only the tiles that changed since the last one, as the tiles let go of it
+/// once the tick is done. Tiles report their changes when their outbox is taken, so a change made outside of a tick
+/// and not through the game shows up a tick later.
+async fn world_snapshot(state: &mut GameState) -> Arc<WorldSnapshot> {
+    let changed = mem::take(&mut state.changed_data);
+
+    if changed.is_empty() {
+        return state.world.clone();
+    }
+
+    let tile_entities = changed
+        .iter()
+        .flat_map(|coord| state.tile_entities.get(coord))
+        .collect::<Vec<_>>();
+
+    let results = match multi_call_iter(
+        tile_entities.iter().cloned(),
+        tile_entities.len(),
+        TileEntityMsg::GetDataWithCoord,
+        None,
+    )
+    .await
+    {
+        Ok(results) => results,
+        Err(e) => {
+            log::error!("{e:?}");
+
+            state.changed_data = changed;
+
+            return state.world.clone();
+        }
+    };
+
+    let world = Arc::make_mut(&mut state.world);
+
+    // the tiles that were removed have no data to fetch
+    for coord in &changed {
+        match state.map.tiles.get(coord) {
+            Some(id) => world.tiles.insert(*coord, *id),
+            None => world.tiles.remove(coord),
+        };
+        world.data.remove(coord);
+    }
+
+    for result in results {
+        if let CallResult::Success((coord, data)) = result {
+            world.data.insert(coord, Arc::new(data));
+        }
+    }
+
+    state.world.clone()
+}
+
+/// Records the transactions and statistics in the outbox, and sorts the rest into the messages to deliver right away and the transaction results to deliver at the end of the tick.
+fn sort_outbox(
+    state: &mut GameState,
+    outbox: Vec<GameMsg>,
+    results: &mut Vec<(TileCoord, TileEntityMsg)>,
+) -> Vec<(TileCoord, TileEntityMsg)> {
+    let mut messages = Vec::new();
+
+    for msg in outbox {
+        match msg {
+            ForwardMsgToTile(coord, msg @ TileEntityMsg::TransactionResult { .. }) => {
+                results.push((coord, msg));
+            }
+            ForwardMsgToTile(coord, msg) => {
+                messages.push((coord, msg));
+            }
+            RecordTransaction(stack, source_coord, coord) => {
+                record_transaction(state, stack, source_coord, coord);
+            }
+            RecordStatistic(coord, stack, kind) => {
+                state.statistics.record(coord, stack, kind);
+            }
+            TileDataChanged(coord) => {
+                data_changed(state, coord);
+            }
+            _ => {}
+        }
+    }
+
+    messages
+}
+
+/// Sends the messages to their tile entities, and returns the coordinates of the tiles that received any, in order.
+fn send_to_tiles(state: &GameState, messages: Vec<(TileCoord, TileEntityMsg)>) -> Vec<TileCoord> {
+    let mut received = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (coord, msg) in messages {
+        if let Some(tile_entity) = state.tile_entities.get(&coord) {
+            if let Err(e) = tile_entity.send_message(msg) {
+                log::error!("{e:?}");
+            } else if seen.insert(coord) {
+                received.push(coord);
+            }
+        }
+    }
+
+    received
+}
+
+/// Runs the phases of one tick, each of which completes before the next one starts:
+/// 1. every tile entity handles the tick, in coordinate order, with a snapshot of the map taken before any of them.
+/// 2. the transactions and extract requests sent out get delivered in waves, until no more are sent or MAX_TRANSACTION_WAVES is reached.
+/// 3. the transaction results get delivered to the tiles that started the transactions.
+///
+/// The messages still being passed on after the last wave, and the ones sent out while handling the results,
+/// are delivered in the first wave of the next tick.
+async fn inner_tick(state: &mut GameState) {
+    let mut coords = state.tile_entities.keys().cloned().collect::<Vec<_>>();
+    coords.sort_by_key(|coord| (coord.x, coord.y));
+
+    let world = world_snapshot(state).await;
+
+    for coord in &coords {
+        if let Err(e) = state.tile_entities[coord].send_message(TileEntityMsg::Tick {
             tick_count: state.tick_count,
+            world: world.clone(),
         }) {
             log::error!("{e:?}");
         }
-    });
+    }
+
+    let mut outbox = mem::take(&mut state.leftover_messages);
+    outbox.extend(take_outboxes(state, &coords).await);
+
+    let mut results = Vec::new();
+    let mut messages = sort_outbox(state, outbox, &mut results);
 
+    for _ in 0..MAX_TRANSACTION_WAVES {
+        if messages.is_empty() {
+            break;
+        }
+
+        let received = send_to_tiles(state, messages);
+        let outbox = take_outboxes(state, &received).await;
+        messages = sort_outbox(state, outbox, &mut results);
+    }
+
+    if !messages.is_empty() {
+        log::warn!(
+            "{} messages were still being passed on at the end of tick {}, delivering them in the next tick",
+            messages.len(),
+            state.tick_count
+        );
+    }
+
+    let received = send_to_tiles(state, results);
+    let outbox = take_outboxes(state, &received).await;
+
+    let mut results = Vec::new();
+    messages.extend(sort_outbox(state, outbox, &mut results));
+    messages.extend(results);
+
+    state.leftover_messages = messages
+        .into_iter()
+        .map(|(coord, msg)| ForwardMsgToTile(coord, msg))
+        .collect();
+
+    state.statistics.end_tick();
     state.tick_count = state.tick_count.wrapping_add(1);
 }
 
 /// Runs the game for one tick, logging if the tick is too long.
-pub fn tick(state: &mut GameState) {
+pub async fn tick(state: &mut GameState) {
     let start = Instant::now();
-    inner_tick(state);
+    inner_tick(state).await;
     let finish = Instant::now();
 
     let tick_time = finish - start;
@@ -647,17 +1435,27 @@ pub fn tick(state: &mut GameState) {
     }
 }
 
-impl Default for GameState {
-    fn default() -> Self {
+impl GameState {
+    fn new(resource_man: Arc<ResourceManager>) -> Self {
         Self {
+            resource_man,
+
             tick_count: 0,
             stopped: false,
 
             map: Map::new_empty("".to_string()),
             tile_entities: Default::default(),
+            leftover_messages: Default::default(),
+            world: Default::default(),
+            changed_data: Default::default(),
 
-            undo_steps: Default::default(),
+            history: Default::default(),
             transaction_records: Default::default(),
+            statistics: Default::default(),
+
+            autosave_interval: Duration::ZERO,
+            last_autosave: Instant::now(),
+            autosave: None,
         }
     }
 }
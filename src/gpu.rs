@@ -85,17 +85,7 @@ pub fn init_gpu_resources(
         source: ShaderSource::Wgsl(resource_man.shaders["intermediate"].as_str().into()),
     });
 
-    let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
-        label: Some("Vertex Buffer"),
-        contents: bytemuck::cast_slice(vertices.as_slice()),
-        usage: BufferUsages::VERTEX,
-    });
-
-    let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
-        label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(indices.as_slice()),
-        usage: BufferUsages::INDEX,
-    });
+    let global_buffers = GlobalBuffers::new(device, vertices, indices);
 
     let ssao_noise_map = image::load_from_memory(SSAO_NOISE_MAP)
         .unwrap()
@@ -947,15 +937,7 @@ pub fn init_gpu_resources(
 
     shared.create(device, config, &mut render);
 
-    (
-        shared,
-        render,
-        GlobalBuffers {
-            vertex_buffer,
-            index_buffer,
-        },
-        gui_resources,
-    )
+    (shared, render, global_buffers, gui_resources)
 }
 
 pub fn compile_instances<T: Clone + Send>(
@@ -1487,6 +1469,28 @@ pub struct GlobalBuffers {
     pub index_buffer: Buffer,
 }
 
+impl GlobalBuffers {
+    /// Uploads the vertices and indices of all the models.
+    pub fn new(device: &Device, vertices: Vec<Vertex>, indices: Vec<u16>) -> Self {
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices.as_slice()),
+            usage: BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(indices.as_slice()),
+            usage: BufferUsages::INDEX,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+        }
+    }
+}
+
 #[derive(OptionGetter)]
 pub struct SharedResources {
     pub game_shader: ShaderModule,
@@ -46,6 +46,15 @@ pub fn debugger(
         ui.label(format!(
             "ResourceMan: Tiles={reg_tiles} Items={reg_items} Tags={tags} Functions={functions} Scripts={scripts} Audio={audio} Meshes={meshes}"
         ));
+        if ui
+            .button(
+                resource_man.translates.gui[&resource_man.registry.gui_ids.btn_reload_resources]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            loop_store.reload_resources = true;
+        }
         ui.label(format!("Map \"{map_name}\" ({:?}): {}", map_info.save_time.unwrap_or(SystemTime::UNIX_EPOCH), ron::ser::to_string_pretty(&map_info.data.to_raw(&setup.resource_man.interner), PrettyConfig::default()).unwrap_or("could not format map info".to_string())));
     });
 }
@@ -1,23 +1,23 @@
 use std::borrow::Cow;
-use std::fs;
 
 use egui::load::Bytes;
 use egui::{
     vec2, Align, Align2, Button, Checkbox, ComboBox, Context, Image, ImageSource, RichText,
-    ScrollArea, Slider, TextEdit, TextStyle, Window,
+    ScrollArea, Slider, TextStyle, Window,
 };
 use tokio::runtime::Runtime;
 use winit::event_loop::EventLoopWindowTarget;
 
 use automancy_defs::flexstr::ToSharedStr;
 use automancy_defs::gui::HyperlinkWidget;
+use automancy_defs::id::IdRaw;
 use automancy_defs::log;
-use automancy_resources::{format, format_time};
+use automancy_resources::{format, format_time, ResourceManager};
 
 use crate::event::{shutdown_graceful, EventLoopStorage};
 use crate::game::{load_map, GameMsg};
 use crate::gui::{OptionsMenuState, PopupState, Screen, SubState, TextField};
-use crate::map::{Map, MAIN_MENU};
+use crate::map::{Map, MapCodec, MAIN_MENU};
 use crate::options::AAType;
 use crate::setup::GameSetup;
 use crate::{LOGO, LOGO_PATH, VERSION};
@@ -64,6 +64,8 @@ pub fn main_menu(
                         )
                         .clicked()
                     {
+                        // the thumbnails may have changed since they were last shown
+                        context.forget_all_images();
                         setup.refresh_maps();
                         loop_store.gui_state.switch_screen(Screen::MapLoad)
                     };
@@ -202,7 +204,12 @@ pub fn pause_menu(
                             .unwrap();
 
                         runtime
-                            .block_on(load_map(setup, loop_store, MAIN_MENU.to_string()))
+                            .block_on(load_map(
+                                setup,
+                                loop_store,
+                                MAIN_MENU.to_string(),
+                                Default::default(),
+                            ))
                             .unwrap();
 
                         loop_store.gui_state.switch_screen(Screen::MainMenu)
@@ -213,6 +220,19 @@ pub fn pause_menu(
         });
 }
 
+/// Gets the name of a tile or item in a map summary, which might not exist in the game.
+fn summary_name(resource_man: &ResourceManager, id: &IdRaw) -> String {
+    match resource_man.interner.get(id.to_string()) {
+        Some(id) if resource_man.registry.tiles.contains_key(&id) => {
+            resource_man.tile_name(&id).to_string()
+        }
+        Some(id) if resource_man.registry.items.contains_key(&id) => {
+            resource_man.item_name(&id).to_string()
+        }
+        _ => id.to_string(),
+    }
+}
+
 /// Draws the map loading menu.
 pub fn map_menu(
     runtime: &Runtime,
@@ -231,96 +251,208 @@ pub fn map_menu(
         ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
             let mut dirty = false;
 
-            for ((_, save_time), map_name) in &setup.maps {
+            for ((info, save_time), map_name) in &setup.maps {
                 ui.group(|ui| {
-                    ui.scope(|ui| {
-                        ui.style_mut().override_text_style = Some(TextStyle::Heading);
-                        ui.set_width(300.0);
-
-                        if map_name == loop_store.gui_state.text_field.get(TextField::MapRenaming) {
-                            if ui
-                                .add(
-                                    TextEdit::multiline(
-                                        loop_store.gui_state.text_field.get(TextField::MapRenaming),
+                    ui.horizontal(|ui| {
+                        let thumbnail = Map::thumbnail(map_name);
+
+                        if thumbnail.exists() {
+                            ui.add(
+                                Image::new(ImageSource::Uri(Cow::Owned(format!(
+                                    "file://{}",
+                                    thumbnail.display()
+                                ))))
+                                .max_size(vec2(128.0, 128.0)),
+                            );
+                        }
+
+                        ui.vertical(|ui| {
+                            ui.scope(|ui| {
+                                ui.style_mut().override_text_style = Some(TextStyle::Heading);
+                                ui.set_width(300.0);
+
+                                if ui.selectable_label(false, map_name.as_str()).clicked() {
+                                    *loop_store.gui_state.text_field.get(TextField::MapRenaming) =
+                                        map_name.clone();
+                                    loop_store.gui_state.popup =
+                                        PopupState::MapRename(map_name.clone());
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                if let Some(save_time) = save_time {
+                                    ui.label(format_time(
+                                        *save_time,
+                                        setup.resource_man.translates.gui
+                                            [&setup.resource_man.registry.gui_ids.time_fmt]
+                                            .as_str(),
+                                    ));
+                                }
+
+                                if ui
+                                    .button(
+                                        setup.resource_man.translates.gui
+                                            [&setup.resource_man.registry.gui_ids.btn_load]
+                                            .as_str(),
                                     )
-                                    .desired_rows(1),
-                                )
-                                .lost_focus()
-                            {
-                                *loop_store.gui_state.text_field.get(TextField::MapRenaming) =
-                                    loop_store
-                                        .gui_state
-                                        .text_field
-                                        .get(TextField::MapRenaming)
-                                        .chars()
-                                        .filter(|v| v.is_alphanumeric())
-                                        .collect();
-
-                                if fs::rename(
-                                    Map::path(map_name),
-                                    Map::path(
-                                        loop_store.gui_state.text_field.get(TextField::MapRenaming),
-                                    ),
-                                )
-                                .is_ok()
+                                    .clicked()
+                                {
+                                    match Map::missing_ids(&setup.resource_man, map_name) {
+                                        Some(missing) if !missing.is_empty() => {
+                                            loop_store.gui_state.missing_ids = missing
+                                                .into_iter()
+                                                .map(|(id, kind)| (id, kind, None))
+                                                .collect();
+                                            loop_store.gui_state.popup =
+                                                PopupState::MissingIds(map_name.clone());
+                                        }
+                                        _ => {
+                                            runtime
+                                                .block_on(load_map(
+                                                    setup,
+                                                    loop_store,
+                                                    map_name.clone(),
+                                                    Default::default(),
+                                                ))
+                                                .unwrap();
+
+                                            loop_store.gui_state.switch_screen(Screen::Ingame);
+                                        }
+                                    }
+                                }
+
+                                if ui
+                                    .button(
+                                        setup.resource_man.translates.gui
+                                            [&setup.resource_man.registry.gui_ids.btn_delete]
+                                            .as_str(),
+                                    )
+                                    .clicked()
                                 {
-                                    log::info!(
-                                        "Renamed map {map_name} to {}",
-                                        loop_store.gui_state.text_field.get(TextField::MapRenaming)
-                                    );
+                                    loop_store.gui_state.popup =
+                                        PopupState::MapDeleteConfirmation(map_name.clone());
 
                                     dirty = true;
-                                } else {
-                                    loop_store.gui_state.popup = PopupState::InvalidName;
                                 }
 
-                                *loop_store.gui_state.text_field.get(TextField::MapRenaming) =
-                                    Default::default();
+                                if ui
+                                    .button(
+                                        setup.resource_man.translates.gui
+                                            [&setup.resource_man.registry.gui_ids.btn_backups]
+                                            .as_str(),
+                                    )
+                                    .clicked()
+                                {
+                                    loop_store.gui_state.popup =
+                                        PopupState::MapBackups(map_name.clone());
+                                }
+
+                                if ui
+                                    .button(
+                                        setup.resource_man.translates.gui
+                                            [&setup.resource_man.registry.gui_ids.btn_duplicate]
+                                            .as_str(),
+                                    )
+                                    .clicked()
+                                {
+                                    *loop_store.gui_state.text_field.get(TextField::MapRenaming) =
+                                        format!("{map_name}_copy");
+                                    loop_store.gui_state.popup =
+                                        PopupState::MapDuplicate(map_name.clone());
+                                }
+
+                                ui.label(
+                                    setup.resource_man.translates.gui
+                                        [&setup.resource_man.registry.gui_ids.lbl_map_codec]
+                                        .as_str(),
+                                );
+
+                                let mut codec = info.codec;
+
+                                ComboBox::from_id_source(map_name)
+                                    .selected_text(format!("{codec:?}"))
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut codec, MapCodec::Ron, "Ron");
+                                        ui.selectable_value(
+                                            &mut codec,
+                                            MapCodec::Bincode,
+                                            "Bincode",
+                                        );
+                                    });
+
+                                if codec != info.codec {
+                                    if let Err(e) = Map::convert(map_name, codec) {
+                                        log::error!("Could not convert map {map_name}: {e:?}");
+
+                                        setup.resource_man.error_man.push(
+                                            (
+                                                setup.resource_man.registry.err_ids.unwritable_map,
+                                                vec![map_name.clone(), e.to_string()],
+                                            ),
+                                            &setup.resource_man,
+                                        );
+                                    }
+
+                                    dirty = true;
+                                }
+                            });
+
+                            if !info.description.is_empty() {
+                                ui.label(info.description.as_str());
                             }
-                        } else if ui.selectable_label(false, map_name.as_str()).clicked() {
-                            *loop_store.gui_state.text_field.get(TextField::MapRenaming) =
-                                map_name.clone();
-                        }
-                    });
 
-                    ui.horizontal(|ui| {
-                        if let Some(save_time) = save_time {
-                            ui.label(format_time(
-                                *save_time,
-                                setup.resource_man.translates.gui
-                                    [&setup.resource_man.registry.gui_ids.time_fmt]
-                                    .as_str(),
-                            ));
-                        }
+                            ui.horizontal(|ui| {
+                                let minutes = info.play_time / 60;
 
-                        if ui
-                            .button(
-                                setup.resource_man.translates.gui
-                                    [&setup.resource_man.registry.gui_ids.btn_load]
-                                    .as_str(),
-                            )
-                            .clicked()
-                        {
-                            runtime
-                                .block_on(load_map(setup, loop_store, map_name.clone()))
-                                .unwrap();
+                                ui.label(format(
+                                    setup.resource_man.translates.gui
+                                        [&setup.resource_man.registry.gui_ids.lbl_play_time]
+                                        .as_str(),
+                                    &[
+                                        (minutes / 60).to_string().as_str(),
+                                        (minutes % 60).to_string().as_str(),
+                                    ],
+                                ));
 
-                            loop_store.gui_state.switch_screen(Screen::Ingame);
-                        }
+                                let items = info.summary.items.values().sum::<u64>();
 
-                        if ui
-                            .button(
-                                setup.resource_man.translates.gui
-                                    [&setup.resource_man.registry.gui_ids.btn_delete]
-                                    .as_str(),
-                            )
-                            .clicked()
-                        {
-                            loop_store.gui_state.popup =
-                                PopupState::MapDeleteConfirmation(map_name.clone());
+                                ui.label(format(
+                                    setup.resource_man.translates.gui
+                                        [&setup.resource_man.registry.gui_ids.lbl_map_summary]
+                                        .as_str(),
+                                    &[
+                                        info.tile_count.to_string().as_str(),
+                                        items.to_string().as_str(),
+                                    ],
+                                ))
+                                .on_hover_ui(|ui| {
+                                    for (id, count) in
+                                        info.summary.tiles.iter().chain(&info.summary.items)
+                                    {
+                                        ui.label(format!(
+                                            "{count} × {}",
+                                            summary_name(&setup.resource_man, id)
+                                        ));
+                                    }
+                                });
 
-                            dirty = true;
-                        }
+                                if ui
+                                    .button(
+                                        setup.resource_man.translates.gui
+                                            [&setup.resource_man.registry.gui_ids.btn_describe]
+                                            .as_str(),
+                                    )
+                                    .clicked()
+                                {
+                                    *loop_store
+                                        .gui_state
+                                        .text_field
+                                        .get(TextField::MapDescription) = info.description.clone();
+                                    loop_store.gui_state.popup =
+                                        PopupState::MapDescription(map_name.clone());
+                                }
+                            });
+                        });
                     });
                 });
             }
@@ -395,6 +527,12 @@ pub fn options_menu(setup: &mut GameSetup, context: &Context, loop_store: &mut E
                 {
                     loop_store.gui_state.substate = SubState::Options(OptionsMenuState::Gui)
                 }
+                if ui
+                    .add(Button::new("Game").min_size(vec2(80.0, 24.0)))
+                    .clicked()
+                {
+                    loop_store.gui_state.substate = SubState::Options(OptionsMenuState::Game)
+                }
                 if ui
                     .add(Button::new("Controls").min_size(vec2(80.0, 24.0)))
                     .clicked()
@@ -536,6 +674,40 @@ pub fn options_menu(setup: &mut GameSetup, context: &Context, loop_store: &mut E
                                 });
                             });
                         }
+                        OptionsMenuState::Game => {
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new("Game").text_style(TextStyle::Heading));
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new("Undo History: "));
+                                    ui.add(
+                                        Slider::new(&mut setup.options.game.undo_depth, 0..=1000)
+                                            .custom_formatter(|n, _| {
+                                                if n == 0.0 {
+                                                    return "Unlimited".to_string();
+                                                };
+                                                format!("{n}")
+                                            }),
+                                    )
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new("Autosave Interval: "));
+                                    ui.add(
+                                        Slider::new(
+                                            &mut setup.options.game.autosave_interval,
+                                            0..=60,
+                                        )
+                                        .custom_formatter(
+                                            |n, _| {
+                                                if n == 0.0 {
+                                                    return "Never".to_string();
+                                                };
+                                                format!("{n} min")
+                                            },
+                                        ),
+                                    )
+                                });
+                            });
+                        }
                         OptionsMenuState::Controls => {
                             ui.label(RichText::new("Controls").text_style(TextStyle::Heading));
                         }
@@ -12,14 +12,16 @@ use wgpu::{CommandBuffer, CommandEncoder, Device, IndexFormat, Queue, RenderPass
 
 use automancy_defs::bytemuck;
 use automancy_defs::glam::vec3;
-use automancy_defs::id::Id;
+use automancy_defs::id::{Id, IdRaw};
 use automancy_defs::math::{Float, Matrix4};
 use automancy_defs::rendering::InstanceData;
 use automancy_resources::ResourceManager;
 
 use crate::gpu;
 use crate::gpu::{AnimationMap, GlobalBuffers, GuiResources};
+use crate::remap::MissingIdKind;
 use crate::renderer::try_add_animation;
+use crate::statistics::StatisticWindow;
 
 pub mod debug;
 pub mod error;
@@ -28,6 +30,8 @@ pub mod item;
 pub mod menu;
 pub mod player;
 pub mod popup;
+pub mod research;
+pub mod statistics;
 pub mod tile_config;
 pub mod tile_selection;
 
@@ -43,6 +47,12 @@ pub struct GuiState {
     pub previous: Option<Screen>,
     pub text_field: TextFieldState,
     pub tile_selection_category: Option<Id>,
+    pub statistics_window: StatisticWindow,
+    pub selected_research: Option<Id>,
+    /// The IDs of the map being loaded that are missing from the game, and what they are replaced with.
+    pub missing_ids: Vec<(IdRaw, MissingIdKind, Option<Id>)>,
+    /// Whether the map being created is in creative mode.
+    pub new_map_creative: bool,
 }
 
 /// The state of the main game GUI.
@@ -67,6 +77,7 @@ pub enum OptionsMenuState {
     Graphics,
     Audio,
     Gui,
+    Game,
     Controls,
 }
 
@@ -77,6 +88,11 @@ pub enum PopupState {
     MapCreate,
     MapDeleteConfirmation(String),
     InvalidName,
+    MissingIds(String),
+    MapBackups(String),
+    MapDescription(String),
+    MapRename(String),
+    MapDuplicate(String),
 }
 
 impl Default for GuiState {
@@ -89,6 +105,10 @@ impl Default for GuiState {
             previous: None,
             text_field: Default::default(),
             tile_selection_category: None,
+            statistics_window: StatisticWindow::Minute,
+            selected_research: None,
+            missing_ids: vec![],
+            new_map_creative: false,
         }
     }
 }
@@ -131,6 +151,7 @@ pub enum TextField {
     Filter,
     MapRenaming,
     MapName,
+    MapDescription,
 }
 
 pub struct TextFieldState {
@@ -145,7 +166,8 @@ impl Default for TextFieldState {
             fields: enum_map! {
                 TextField::Filter => Default::default(),
                 TextField::MapName => Default::default(),
-                TextField::MapRenaming => Default::default()
+                TextField::MapRenaming => Default::default(),
+                TextField::MapDescription => Default::default()
             },
         }
     }
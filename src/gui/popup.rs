@@ -1,15 +1,22 @@
 use std::fs;
+use std::io;
+use std::mem;
 
-use egui::{vec2, Align2, Window};
+use egui::{vec2, Align2, ComboBox, ScrollArea, Window};
+use hashbrown::HashSet;
 use tokio::runtime::Runtime;
 
 use automancy_defs::gui::Gui;
+use automancy_defs::id::{Id, IdRaw};
 use automancy_defs::log;
+use automancy_resources::data::Data;
+use automancy_resources::{format_time, ResourceManager};
 
 use crate::event::EventLoopStorage;
 use crate::game::load_map;
 use crate::gui::{PopupState, Screen, TextField};
 use crate::map::Map;
+use crate::remap::MissingIdKind;
 use crate::setup::GameSetup;
 
 pub fn invalid_name_popup(setup: &GameSetup, gui: &mut Gui, loop_store: &mut EventLoopStorage) {
@@ -88,6 +95,261 @@ pub fn map_delete_popup(
     }
 }
 
+/// Draws the popup that lists the autosaves of a map and lets the player restore one of them.
+pub fn map_backups_popup(
+    setup: &mut GameSetup,
+    gui: &mut Gui,
+    loop_store: &mut EventLoopStorage,
+    map_name: &str,
+) {
+    let mut dirty = false;
+
+    Window::new(
+        setup.resource_man.translates.gui[&setup.resource_man.registry.gui_ids.map_backups]
+            .as_str(),
+    )
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    .show(&gui.context, |ui| {
+        let backups = Map::list_backups(map_name);
+
+        if backups.is_empty() {
+            ui.label(
+                setup.resource_man.translates.gui
+                    [&setup.resource_man.registry.gui_ids.lbl_no_backups]
+                    .as_str(),
+            );
+        }
+
+        for (slot, save_time) in backups {
+            ui.horizontal(|ui| {
+                ui.label(format_time(
+                    save_time,
+                    setup.resource_man.translates.gui
+                        [&setup.resource_man.registry.gui_ids.time_fmt]
+                        .as_str(),
+                ));
+
+                if ui
+                    .button(
+                        setup.resource_man.translates.gui
+                            [&setup.resource_man.registry.gui_ids.btn_restore]
+                            .as_str(),
+                    )
+                    .clicked()
+                {
+                    if let Err(e) = Map::restore_backup(map_name, slot) {
+                        log::error!("Could not restore backup {slot} of map {map_name}: {e}");
+                    }
+
+                    dirty = true;
+                    loop_store.gui_state.popup = PopupState::None;
+                }
+            });
+        }
+
+        if ui
+            .button(
+                setup.resource_man.translates.gui[&setup.resource_man.registry.gui_ids.btn_cancel]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            loop_store.gui_state.popup = PopupState::None
+        }
+    });
+
+    if dirty {
+        setup.refresh_maps();
+    }
+}
+
+/// Draws a popup that asks for a new name for a map, and then renames or copies the map to it.
+fn map_name_popup(
+    setup: &mut GameSetup,
+    gui: &mut Gui,
+    loop_store: &mut EventLoopStorage,
+    map_name: &str,
+    title: Id,
+    action: fn(&str, &str) -> io::Result<()>,
+) {
+    let mut dirty = false;
+
+    Window::new(setup.resource_man.translates.gui[&title].as_str())
+        .resizable(false)
+        .collapsible(false)
+        .default_width(250.0)
+        .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+        .show(&gui.context, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(
+                    setup.resource_man.translates.gui
+                        [&setup.resource_man.registry.gui_ids.lbl_map_name]
+                        .as_str(),
+                );
+                ui.text_edit_singleline(
+                    loop_store.gui_state.text_field.get(TextField::MapRenaming),
+                );
+            });
+            if ui
+                .button(
+                    setup.resource_man.translates.gui
+                        [&setup.resource_man.registry.gui_ids.btn_confirm]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                let new_name = Map::sanitize_name(mem::take(
+                    loop_store.gui_state.text_field.get(TextField::MapRenaming),
+                ));
+
+                loop_store.gui_state.popup = match action(map_name, &new_name) {
+                    Ok(()) => PopupState::None,
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => PopupState::InvalidName,
+                    Err(e) => {
+                        log::error!("Could not copy or rename map {map_name} to {new_name}: {e}");
+
+                        setup.resource_man.error_man.push(
+                            (
+                                setup.resource_man.registry.err_ids.unwritable_map,
+                                vec![new_name, e.to_string()],
+                            ),
+                            &setup.resource_man,
+                        );
+
+                        PopupState::None
+                    }
+                };
+
+                dirty = true;
+            }
+            if ui
+                .button(
+                    setup.resource_man.translates.gui
+                        [&setup.resource_man.registry.gui_ids.btn_cancel]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                loop_store
+                    .gui_state
+                    .text_field
+                    .get(TextField::MapRenaming)
+                    .clear();
+                loop_store.gui_state.popup = PopupState::None;
+            }
+        });
+
+    if dirty {
+        setup.refresh_maps();
+    }
+}
+
+/// Draws the map renaming popup.
+pub fn map_rename_popup(
+    setup: &mut GameSetup,
+    gui: &mut Gui,
+    loop_store: &mut EventLoopStorage,
+    map_name: &str,
+) {
+    let title = setup.resource_man.registry.gui_ids.rename_map;
+
+    map_name_popup(setup, gui, loop_store, map_name, title, Map::rename);
+}
+
+/// Draws the popup that copies a map, along with its backups, under a new name.
+pub fn map_duplicate_popup(
+    setup: &mut GameSetup,
+    gui: &mut Gui,
+    loop_store: &mut EventLoopStorage,
+    map_name: &str,
+) {
+    let title = setup.resource_man.registry.gui_ids.duplicate_map;
+
+    map_name_popup(setup, gui, loop_store, map_name, title, Map::duplicate);
+}
+
+/// Draws the popup that edits the description of a map.
+pub fn map_description_popup(
+    setup: &mut GameSetup,
+    gui: &mut Gui,
+    loop_store: &mut EventLoopStorage,
+    map_name: &str,
+) {
+    let mut dirty = false;
+
+    Window::new(
+        setup.resource_man.translates.gui[&setup.resource_man.registry.gui_ids.map_description]
+            .as_str(),
+    )
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    .show(&gui.context, |ui| {
+        ui.text_edit_multiline(
+            loop_store
+                .gui_state
+                .text_field
+                .get(TextField::MapDescription),
+        );
+
+        ui.horizontal(|ui| {
+            if ui
+                .button(
+                    setup.resource_man.translates.gui
+                        [&setup.resource_man.registry.gui_ids.btn_confirm]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                let description = mem::take(
+                    loop_store
+                        .gui_state
+                        .text_field
+                        .get(TextField::MapDescription),
+                );
+
+                if let Err(e) = Map::set_description(map_name, description) {
+                    log::error!("Could not change the description of map {map_name}: {e:?}");
+
+                    setup.resource_man.error_man.push(
+                        (
+                            setup.resource_man.registry.err_ids.unwritable_map,
+                            vec![map_name.to_string(), e.to_string()],
+                        ),
+                        &setup.resource_man,
+                    );
+                }
+
+                dirty = true;
+                loop_store.gui_state.popup = PopupState::None;
+            }
+            if ui
+                .button(
+                    setup.resource_man.translates.gui
+                        [&setup.resource_man.registry.gui_ids.btn_cancel]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                loop_store
+                    .gui_state
+                    .text_field
+                    .get(TextField::MapDescription)
+                    .clear();
+                loop_store.gui_state.popup = PopupState::None;
+            }
+        });
+    });
+
+    if dirty {
+        setup.refresh_maps();
+    }
+}
+
 /// Draws the map creation popup.
 pub fn map_create_popup(
     runtime: &Runtime,
@@ -107,6 +369,11 @@ pub fn map_create_popup(
             ui.label("Name:"); //TODO add this to translation
             ui.text_edit_singleline(loop_store.gui_state.text_field.get(TextField::MapName));
         });
+        ui.checkbox(
+            &mut loop_store.gui_state.new_map_creative,
+            setup.resource_man.translates.gui[&setup.resource_man.registry.gui_ids.lbl_creative]
+                .as_str(),
+        );
         if ui
             .button(
                 setup.resource_man.translates.gui[&setup.resource_man.registry.gui_ids.btn_confirm]
@@ -122,13 +389,28 @@ pub fn map_create_popup(
                     .clone(),
             );
 
-            runtime.block_on(load_map(setup, loop_store, name)).unwrap();
+            // an existing map is loaded as it is, so only a new map gets the chosen mode
+            let exists = Map::path(&name).exists();
+
+            runtime
+                .block_on(load_map(setup, loop_store, name, Default::default()))
+                .unwrap();
+
+            if !exists {
+                if let Some((info, _)) = &loop_store.map_info {
+                    info.blocking_lock().data.insert(
+                        setup.resource_man.registry.data_ids.creative,
+                        Data::Bool(loop_store.gui_state.new_map_creative),
+                    );
+                }
+            }
 
             loop_store
                 .gui_state
                 .text_field
                 .get(TextField::MapName)
                 .clear();
+            loop_store.gui_state.new_map_creative = false;
             loop_store.gui_state.popup = PopupState::None;
             loop_store.gui_state.switch_screen(Screen::Ingame);
         }
@@ -143,3 +425,161 @@ pub fn map_create_popup(
         }
     });
 }
+
+/// The IDs that the missing IDs of a map can be replaced with, besides tiles and items, sorted by their names.
+/// They are collected once when the popup opens, rather than every frame.
+pub struct ReplacementCandidates {
+    scripts: Vec<Id>,
+    data: Vec<Id>,
+}
+
+impl ReplacementCandidates {
+    pub fn new(resource_man: &ResourceManager) -> Self {
+        let mut scripts = resource_man
+            .registry
+            .scripts
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        scripts.sort_by_key(|id| resource_man.script_name(id));
+
+        // only the keys the game and its tiles use can be data keys
+        let mut data = resource_man
+            .registry
+            .data_ids
+            .ids()
+            .into_iter()
+            .chain(
+                resource_man
+                    .registry
+                    .tiles
+                    .values()
+                    .flat_map(|tile| tile.data.keys().cloned()),
+            )
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        data.sort_by_key(|id| resource_man.interner.resolve(*id));
+
+        Self { scripts, data }
+    }
+}
+
+/// Draws the popup that lets the player replace the IDs of a map that are missing from the game before loading it.
+pub fn missing_ids_popup(
+    runtime: &Runtime,
+    setup: &GameSetup,
+    gui: &mut Gui,
+    loop_store: &mut EventLoopStorage,
+    map_name: &str,
+) {
+    let resource_man = setup.resource_man.clone();
+
+    let name = |kind: MissingIdKind, id: &Id| -> String {
+        match kind {
+            MissingIdKind::Tile => resource_man.tile_name(id).to_string(),
+            MissingIdKind::Item => resource_man.item_name(id).to_string(),
+            MissingIdKind::Script => resource_man.script_name(id).to_string(),
+            MissingIdKind::Data => resource_man.interner.resolve(*id).unwrap_or("").to_string(),
+        }
+    };
+
+    let replacements = loop_store
+        .missing_id_candidates
+        .take()
+        .unwrap_or_else(|| ReplacementCandidates::new(&resource_man));
+
+    Window::new(resource_man.translates.gui[&resource_man.registry.gui_ids.missing_ids].as_str())
+        .resizable(false)
+        .collapsible(false)
+        .default_width(400.0)
+        .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+        .show(&gui.context, |ui| {
+            ui.label(
+                resource_man.translates.gui[&resource_man.registry.gui_ids.lbl_missing_ids]
+                    .as_str(),
+            );
+
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for (idx, (id, kind, replacement)) in
+                    loop_store.gui_state.missing_ids.iter_mut().enumerate()
+                {
+                    let kind = *kind;
+
+                    let none = if kind == MissingIdKind::Tile {
+                        resource_man.registry.gui_ids.lbl_keep_placeholder
+                    } else {
+                        resource_man.registry.gui_ids.lbl_leave_out
+                    };
+                    let none = resource_man.translates.gui[&none].to_string();
+
+                    let candidates = match kind {
+                        MissingIdKind::Tile => &resource_man.ordered_tiles,
+                        MissingIdKind::Item => &resource_man.ordered_items,
+                        MissingIdKind::Script => &replacements.scripts,
+                        MissingIdKind::Data => &replacements.data,
+                    };
+
+                    ui.horizontal(|ui| {
+                        ui.label(id.to_string());
+
+                        ComboBox::from_id_source(idx)
+                            .selected_text(
+                                replacement
+                                    .as_ref()
+                                    .map(|id| name(kind, id))
+                                    .unwrap_or_else(|| none.clone()),
+                            )
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(replacement, None, none);
+
+                                for candidate in candidates {
+                                    ui.selectable_value(
+                                        replacement,
+                                        Some(*candidate),
+                                        name(kind, candidate),
+                                    );
+                                }
+                            });
+                    });
+                }
+            });
+
+            if ui
+                .button(
+                    resource_man.translates.gui[&resource_man.registry.gui_ids.btn_confirm]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                let remap = mem::take(&mut loop_store.gui_state.missing_ids)
+                    .into_iter()
+                    .flat_map(|(id, _, replacement)| {
+                        replacement
+                            .and_then(|v| resource_man.interner.resolve(v))
+                            .map(|v| (id, IdRaw::parse(v)))
+                    })
+                    .collect();
+
+                runtime
+                    .block_on(load_map(setup, loop_store, map_name.to_string(), remap))
+                    .unwrap();
+
+                loop_store.gui_state.popup = PopupState::None;
+                loop_store.gui_state.switch_screen(Screen::Ingame);
+            }
+            if ui
+                .button(
+                    resource_man.translates.gui[&resource_man.registry.gui_ids.btn_cancel].as_str(),
+                )
+                .clicked()
+            {
+                loop_store.gui_state.missing_ids.clear();
+                loop_store.gui_state.popup = PopupState::None
+            }
+        });
+
+    if matches!(loop_store.gui_state.popup, PopupState::MissingIds(_)) {
+        loop_store.missing_id_candidates = Some(replacements);
+    }
+}
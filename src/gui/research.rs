@@ -0,0 +1,224 @@
+use egui::{
+    vec2, Align2, Button, Context, Pos2, Rect, RichText, ScrollArea, Sense, Stroke, Vec2, Window,
+};
+use hashbrown::HashMap;
+
+use automancy_defs::colors;
+use automancy_defs::graph::algo::toposort;
+use automancy_defs::graph::graph::NodeIndex;
+use automancy_defs::graph::Direction;
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::ItemStack;
+use automancy_resources::data::DataMap;
+use automancy_resources::ResourceManager;
+
+use crate::event::EventLoopStorage;
+use crate::gui::item::draw_item;
+use crate::gui::SMALL_ICON_SIZE;
+use crate::setup::GameSetup;
+
+const NODE_SIZE: Vec2 = vec2(160.0, 40.0);
+const NODE_SPACING: Vec2 = vec2(60.0, 20.0);
+
+/// Places the researches in columns, each one to the right of all the researches leading to it.
+fn layout(resource_man: &ResourceManager) -> HashMap<NodeIndex, Pos2> {
+    let graph = &resource_man.registry.researches;
+
+    let order = toposort(graph, None).unwrap_or_else(|_| graph.node_indices().collect());
+
+    let mut depths = HashMap::new();
+    for index in order {
+        let depth = graph
+            .neighbors_directed(index, Direction::Incoming)
+            .flat_map(|parent| depths.get(&parent).map(|depth| depth + 1))
+            .max()
+            .unwrap_or(0usize);
+
+        depths.insert(index, depth);
+    }
+
+    let mut columns = Vec::<Vec<NodeIndex>>::new();
+    for (index, depth) in depths {
+        if columns.len() <= depth {
+            columns.resize(depth + 1, vec![]);
+        }
+
+        columns[depth].push(index);
+    }
+
+    let mut positions = HashMap::new();
+    for (x, column) in columns.iter_mut().enumerate() {
+        column.sort_by_key(|index| resource_man.research_str(&graph[*index].name));
+
+        for (y, index) in column.iter().enumerate() {
+            positions.insert(
+                *index,
+                Pos2::new(
+                    x as f32 * (NODE_SIZE.x + NODE_SPACING.x),
+                    y as f32 * (NODE_SIZE.y + NODE_SPACING.y),
+                ),
+            );
+        }
+    }
+
+    positions
+}
+
+/// Gets the name of the tile, script or category a research unlocks.
+fn unlock_name(resource_man: &ResourceManager, id: Id) -> &str {
+    if resource_man.registry.scripts.contains_key(&id) {
+        resource_man.script_name(&id)
+    } else if resource_man.registry.categories.contains_key(&id) {
+        resource_man.category_name(&id)
+    } else {
+        resource_man.tile_name(&id)
+    }
+}
+
+/// Draws the research graph, and the details of the selected research.
+pub fn research_menu(
+    setup: &GameSetup,
+    loop_store: &mut EventLoopStorage,
+    context: &Context,
+    game_data: &mut DataMap,
+) {
+    let resource_man = &setup.resource_man;
+    let graph = &resource_man.registry.researches;
+
+    Window::new(resource_man.translates.gui[&resource_man.registry.gui_ids.research_menu].as_str())
+        .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+        .resizable(false)
+        .collapsible(false)
+        .default_width(600.0)
+        .show(context, |ui| {
+            let positions = layout(resource_man);
+
+            ScrollArea::both().max_height(300.0).show(ui, |ui| {
+                let size = positions
+                    .values()
+                    .fold(Vec2::ZERO, |size, pos| size.max(pos.to_vec2() + NODE_SIZE));
+                let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+
+                for (source, target) in graph
+                    .edge_indices()
+                    .flat_map(|edge| graph.edge_endpoints(edge))
+                {
+                    let from = rect.min
+                        + positions[&source].to_vec2()
+                        + vec2(NODE_SIZE.x, NODE_SIZE.y / 2.0);
+                    let to = rect.min + positions[&target].to_vec2() + vec2(0.0, NODE_SIZE.y / 2.0);
+
+                    let color = if resource_man.is_research_unlocked(source, game_data) {
+                        colors::LIGHT_BLUE
+                    } else {
+                        colors::GRAY
+                    };
+
+                    ui.painter()
+                        .line_segment([from, to], Stroke::new(2.0, color));
+                }
+
+                for (index, pos) in &positions {
+                    let research = &graph[*index];
+
+                    let name = RichText::new(resource_man.research_str(&research.name));
+                    let name = if resource_man.is_research_unlocked(*index, game_data) {
+                        name.color(colors::LIGHT_BLUE)
+                    } else if resource_man.is_research_available(*index, game_data) {
+                        name
+                    } else {
+                        name.color(colors::GRAY)
+                    };
+
+                    let button = Button::new(name)
+                        .selected(loop_store.gui_state.selected_research == Some(research.id));
+
+                    if ui
+                        .put(
+                            Rect::from_min_size(rect.min + pos.to_vec2(), NODE_SIZE),
+                            button,
+                        )
+                        .clicked()
+                    {
+                        loop_store.gui_state.selected_research = Some(research.id);
+                    }
+                }
+            });
+
+            let Some(index) = loop_store
+                .gui_state
+                .selected_research
+                .and_then(|id| resource_man.registry.researches_id_map.get(&id).cloned())
+            else {
+                return;
+            };
+            let research = &graph[index];
+
+            ui.separator();
+
+            ui.heading(resource_man.research_str(&research.name));
+            ui.label(resource_man.research_str(&research.description));
+
+            if !research.unlocks.is_empty() {
+                ui.label(
+                    resource_man.translates.gui
+                        [&resource_man.registry.gui_ids.lbl_research_unlocks]
+                        .as_str(),
+                );
+
+                for id in &research.unlocks {
+                    ui.label(unlock_name(resource_man, *id));
+                }
+            }
+
+            if !research.required_items.is_empty() {
+                ui.label(
+                    resource_man.translates.gui[&resource_man.registry.gui_ids.lbl_research_cost]
+                        .as_str(),
+                );
+
+                for (id, amount) in &research.required_items {
+                    if let Some(item) = resource_man.registry.items.get(id) {
+                        draw_item(
+                            ui,
+                            resource_man,
+                            None,
+                            ItemStack {
+                                item: *item,
+                                amount: *amount,
+                            },
+                            SMALL_ICON_SIZE,
+                            true,
+                        );
+                    }
+                }
+            }
+
+            if resource_man.is_research_unlocked(index, game_data) {
+                ui.colored_label(
+                    colors::LIGHT_BLUE,
+                    resource_man.translates.gui
+                        [&resource_man.registry.gui_ids.lbl_research_unlocked]
+                        .as_str(),
+                );
+            } else if !resource_man.is_research_available(index, game_data) {
+                ui.colored_label(
+                    colors::GRAY,
+                    resource_man.translates.gui
+                        [&resource_man.registry.gui_ids.lbl_research_unavailable]
+                        .as_str(),
+                );
+            } else if ui
+                .add_enabled(
+                    resource_man.has_research_items(index, game_data),
+                    Button::new(
+                        resource_man.translates.gui[&resource_man.registry.gui_ids.btn_unlock]
+                            .as_str(),
+                    ),
+                )
+                .clicked()
+            {
+                resource_man.unlock_research(research.id, game_data);
+            }
+        });
+}
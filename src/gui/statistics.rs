@@ -0,0 +1,144 @@
+use std::hash::Hash;
+
+use egui::{CollapsingHeader, Context, Grid, ScrollArea, Ui, Window};
+use hashbrown::HashMap;
+use ractor::rpc::CallResult;
+use tokio::runtime::Runtime;
+
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::ItemStack;
+use automancy_resources::ResourceManager;
+
+use crate::event::EventLoopStorage;
+use crate::game::GameMsg;
+use crate::gui::item::draw_item;
+use crate::gui::SMALL_ICON_SIZE;
+use crate::setup::GameSetup;
+use crate::statistics::{ItemCounts, StatisticKind, StatisticWindow};
+
+/// Draws a table of the counts of each item.
+fn item_counts(
+    ui: &mut Ui,
+    resource_man: &ResourceManager,
+    id_source: impl Hash,
+    counts: HashMap<Id, ItemCounts>,
+) {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a, _), (b, _)| resource_man.item_name(a).cmp(resource_man.item_name(b)));
+
+    Grid::new(id_source).striped(true).show(ui, |ui| {
+        ui.label("");
+        for label in [
+            resource_man.registry.gui_ids.lbl_produced,
+            resource_man.registry.gui_ids.lbl_consumed,
+            resource_man.registry.gui_ids.lbl_moved,
+        ] {
+            ui.label(resource_man.translates.gui[&label].as_str());
+        }
+        ui.end_row();
+
+        for (id, counts) in counts {
+            if let Some(item) = resource_man.registry.items.get(&id) {
+                draw_item(
+                    ui,
+                    resource_man,
+                    None,
+                    ItemStack {
+                        item: *item,
+                        amount: 0,
+                    },
+                    SMALL_ICON_SIZE,
+                    true,
+                );
+            } else {
+                ui.label(resource_man.item_name(&id));
+            }
+
+            for kind in [
+                StatisticKind::Produced,
+                StatisticKind::Consumed,
+                StatisticKind::Moved,
+            ] {
+                ui.label(counts[kind].to_string());
+            }
+            ui.end_row();
+        }
+    });
+}
+
+/// Draws the production statistics GUI.
+pub fn statistics(
+    runtime: &Runtime,
+    setup: &GameSetup,
+    loop_store: &mut EventLoopStorage,
+    context: &Context,
+) {
+    let resource_man = &setup.resource_man;
+
+    Window::new(
+        resource_man.translates.gui[&resource_man.registry.gui_ids.production_stats].as_str(),
+    )
+    .resizable(false)
+    .default_width(400.0)
+    .show(context, |ui| {
+        ui.horizontal(|ui| {
+            for (window, label) in [
+                (
+                    StatisticWindow::Second,
+                    resource_man.registry.gui_ids.lbl_last_second,
+                ),
+                (
+                    StatisticWindow::Minute,
+                    resource_man.registry.gui_ids.lbl_last_minute,
+                ),
+                (
+                    StatisticWindow::Hour,
+                    resource_man.registry.gui_ids.lbl_last_hour,
+                ),
+            ] {
+                ui.selectable_value(
+                    &mut loop_store.gui_state.statistics_window,
+                    window,
+                    resource_man.translates.gui[&label].as_str(),
+                );
+            }
+        });
+
+        ui.separator();
+
+        let window = loop_store.gui_state.statistics_window;
+        let Ok(CallResult::Success(report)) = runtime.block_on(
+            setup
+                .game
+                .call(|reply| GameMsg::GetStatistics(window, reply), None),
+        ) else {
+            return;
+        };
+
+        ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            ui.heading(
+                resource_man.translates.gui[&resource_man.registry.gui_ids.lbl_stats_by_item]
+                    .as_str(),
+            );
+            item_counts(ui, resource_man, "statistics_items", report.items);
+
+            ui.separator();
+
+            ui.heading(
+                resource_man.translates.gui[&resource_man.registry.gui_ids.lbl_stats_by_tile]
+                    .as_str(),
+            );
+
+            let mut tiles = report.tiles.into_iter().collect::<Vec<_>>();
+            tiles.sort_by_key(|(coord, _)| (coord.x, coord.y));
+
+            for (coord, (id, counts)) in tiles {
+                CollapsingHeader::new(format!("{} {coord}", resource_man.tile_name(&id)))
+                    .id_source(coord)
+                    .show(ui, |ui| {
+                        item_counts(ui, resource_man, coord, counts);
+                    });
+            }
+        });
+    });
+}
@@ -3,7 +3,6 @@ use std::time::Instant;
 use egui::{vec2, DragValue, Margin, Ui, Window};
 use egui::{Context, Frame};
 use ractor::rpc::CallResult;
-use ractor::ActorRef;
 use tokio::runtime::Runtime;
 
 use automancy_defs::coord::TileCoord;
@@ -13,14 +12,37 @@ use automancy_resources::data::inventory::Inventory;
 use automancy_resources::data::stack::ItemStack;
 use automancy_resources::data::{Data, DataMap};
 use automancy_resources::types::tile::TileDef;
-use automancy_resources::ResourceManager;
+use automancy_resources::{format, ResourceManager};
 
 use crate::event::EventLoopStorage;
+use crate::game::GameMsg;
 use crate::gui::item::draw_item;
 use crate::gui::{info_hover, TextField, MEDIUM_ICON_SIZE, SMALL_ICON_SIZE};
+use crate::map::is_creative;
 use crate::setup::GameSetup;
 use crate::tile_entity::TileEntityMsg;
 
+/// Sets the data values of the tile through the game, so that the change can be undone.
+fn set_data(setup: &GameSetup, coord: TileCoord, values: Vec<(Id, Option<Data>)>) {
+    set_data_recorded(setup, coord, values, true);
+}
+
+fn set_data_recorded(
+    setup: &GameSetup,
+    coord: TileCoord,
+    values: Vec<(Id, Option<Data>)>,
+    record: bool,
+) {
+    setup
+        .game
+        .send_message(GameMsg::SetTileData {
+            coord,
+            values,
+            record,
+        })
+        .unwrap();
+}
+
 /// Draws the direction selector.
 pub fn add_direction(ui: &mut Ui, target_coord: &mut Option<TileCoord>, n: u8) {
     let coord = match n {
@@ -48,12 +70,7 @@ pub fn add_direction(ui: &mut Ui, target_coord: &mut Option<TileCoord>, n: u8) {
     );
 }
 
-fn config_target(
-    ui: &mut Ui,
-    setup: &GameSetup,
-    data: &DataMap,
-    tile_entity: ActorRef<TileEntityMsg>,
-) {
+fn config_target(ui: &mut Ui, setup: &GameSetup, data: &DataMap, coord: TileCoord) {
     let current_target_coord = data
         .get(&setup.resource_man.registry.data_ids.target)
         .cloned()
@@ -86,20 +103,14 @@ fn config_target(
     });
 
     if new_target_coord != current_target_coord {
-        if let Some(target_coord) = new_target_coord {
-            tile_entity
-                .send_message(TileEntityMsg::SetDataValue(
-                    setup.resource_man.registry.data_ids.target,
-                    Data::Coord(target_coord),
-                ))
-                .unwrap();
-        } else {
-            tile_entity
-                .send_message(TileEntityMsg::RemoveData(
-                    setup.resource_man.registry.data_ids.target,
-                ))
-                .unwrap();
-        }
+        set_data(
+            setup,
+            coord,
+            vec![(
+                setup.resource_man.registry.data_ids.target,
+                new_target_coord.map(Data::Coord),
+            )],
+        );
     }
 }
 
@@ -132,8 +143,9 @@ fn config_linking(
 fn config_amount(
     ui: &mut Ui,
     setup: &GameSetup,
+    loop_store: &mut EventLoopStorage,
     data: &DataMap,
-    tile_entity: ActorRef<TileEntityMsg>,
+    coord: TileCoord,
     tile_info: &TileDef,
 ) {
     let Some(Data::Amount(max_amount)) = tile_info
@@ -153,8 +165,9 @@ fn config_amount(
     };
 
     let mut new_amount = current_amount;
+    let amount = setup.resource_man.registry.data_ids.amount;
 
-    ui.add(
+    let response = ui.add(
         DragValue::new(&mut new_amount)
             .clamp_range(0..=max_amount)
             .speed(1.0)
@@ -164,13 +177,36 @@ fn config_amount(
             ),
     );
 
+    if response.drag_started() || response.gained_focus() {
+        loop_store.config_amount_before = Some(current_amount);
+    }
+
+    // while the amount is dragged or typed in, only the amount it ends up at is recorded
+    let editing = loop_store.config_amount_before.is_some();
+
     if new_amount != current_amount {
-        tile_entity
-            .send_message(TileEntityMsg::SetDataValue(
-                setup.resource_man.registry.data_ids.amount,
-                Data::Amount(new_amount),
-            ))
-            .unwrap();
+        set_data_recorded(
+            setup,
+            coord,
+            vec![(amount, Some(Data::Amount(new_amount)))],
+            !editing,
+        );
+    }
+
+    if response.drag_released() || response.lost_focus() {
+        if let Some(before) = loop_store.config_amount_before.take() {
+            if before != new_amount {
+                // the recorded step goes back to the amount from before the edit, undoing the whole edit at once
+                setup
+                    .game
+                    .send_message(GameMsg::RecordHistory(vec![GameMsg::SetTileData {
+                        coord,
+                        values: vec![(amount, Some(Data::Amount(before)))],
+                        record: true,
+                    }]))
+                    .unwrap();
+            }
+        }
     }
 }
 
@@ -180,7 +216,7 @@ fn takeable_item(
     loop_store: &mut EventLoopStorage,
     mut buffer: Inventory,
     game_data: &mut DataMap,
-    tile_entity: ActorRef<TileEntityMsg>,
+    coord: TileCoord,
 ) {
     let Data::Inventory(inventory) = game_data
         .entry(setup.resource_man.registry.data_ids.player_inventory)
@@ -217,22 +253,57 @@ fn takeable_item(
     }
 
     if dirty {
-        tile_entity
-            .send_message(TileEntityMsg::SetDataValue(
-                setup.resource_man.registry.data_ids.buffer,
-                Data::Inventory(buffer),
+        // sent through the game, so that the next save knows the tile changed
+        setup
+            .game
+            .send_message(GameMsg::ForwardMsgToTile(
+                coord,
+                TileEntityMsg::SetDataValue(
+                    setup.resource_man.registry.data_ids.buffer,
+                    Data::Inventory(buffer),
+                ),
             ))
             .unwrap();
     }
 }
 
+/// Draws the button that fills a storage with its item, which is only available in creative mode.
+fn creative_fill(ui: &mut Ui, setup: &GameSetup, data: &DataMap, coord: TileCoord) {
+    let Some(Data::Id(item)) = data.get(&setup.resource_man.registry.data_ids.item) else {
+        return;
+    };
+    let Some(Data::Amount(amount)) = data.get(&setup.resource_man.registry.data_ids.amount) else {
+        return;
+    };
+
+    if ui
+        .button(
+            setup.resource_man.translates.gui[&setup.resource_man.registry.gui_ids.btn_fill]
+                .as_str(),
+        )
+        .clicked()
+    {
+        let mut buffer = Inventory::default();
+        buffer.insert(*item, *amount);
+
+        set_data(
+            setup,
+            coord,
+            vec![(
+                setup.resource_man.registry.data_ids.buffer,
+                Some(Data::Inventory(buffer)),
+            )],
+        );
+    }
+}
+
 fn config_item(
     ui: &mut Ui,
     setup: &GameSetup,
     loop_store: &mut EventLoopStorage,
     data: &DataMap,
     item_type: Id,
-    tile_entity: ActorRef<TileEntityMsg>,
+    coord: TileCoord,
     tile_info: &TileDef,
 ) {
     let current_item = data
@@ -255,7 +326,7 @@ fn config_item(
                 .as_str(),
         );
 
-        config_amount(ui, setup, data, tile_entity.clone(), tile_info);
+        config_amount(ui, setup, loop_store, data, coord, tile_info);
     });
 
     if let Some(stack) = current_item
@@ -290,17 +361,17 @@ fn config_item(
 
     if new_item != current_item {
         if let Some(item) = new_item {
-            tile_entity
-                .send_message(TileEntityMsg::SetDataValue(
-                    setup.resource_man.registry.data_ids.item,
-                    Data::Id(item),
-                ))
-                .unwrap();
-            tile_entity
-                .send_message(TileEntityMsg::RemoveData(
-                    setup.resource_man.registry.data_ids.buffer,
-                ))
-                .unwrap();
+            set_data(
+                setup,
+                coord,
+                vec![
+                    (
+                        setup.resource_man.registry.data_ids.item,
+                        Some(Data::Id(item)),
+                    ),
+                    (setup.resource_man.registry.data_ids.buffer, None),
+                ],
+            );
         }
     }
 }
@@ -313,6 +384,26 @@ fn draw_script_info(ui: &mut Ui, setup: &GameSetup, script: Option<Id>) {
     ui.vertical(|ui| {
         ui.spacing_mut().item_spacing = vec2(0.0, 0.0);
 
+        if let Some(adjacent) = script.adjacent {
+            let resource_man = &setup.resource_man;
+
+            // tags have no translations, so they are shown by their ID
+            let name = if resource_man.registry.tiles.contains_key(&adjacent) {
+                resource_man.tile_name(&adjacent)
+            } else {
+                resource_man
+                    .interner
+                    .resolve(adjacent)
+                    .unwrap_or(resource_man.translates.unnamed.as_str())
+            };
+
+            ui.label(format(
+                resource_man.translates.gui[&resource_man.registry.gui_ids.lbl_script_adjacent]
+                    .as_str(),
+                &[name],
+            ));
+        }
+
         if let Some(inputs) = &script.instructions.inputs {
             for input in inputs {
                 draw_item(
@@ -345,8 +436,15 @@ fn config_script(
     loop_store: &mut EventLoopStorage,
     data: &DataMap,
     scripts: &[Id],
-    tile_entity: ActorRef<TileEntityMsg>,
+    game_data: &DataMap,
+    coord: TileCoord,
 ) {
+    let scripts = scripts
+        .iter()
+        .filter(|id| setup.resource_man.is_unlocked(**id, game_data))
+        .cloned()
+        .collect::<Vec<_>>();
+
     let current_script = data
         .get(&setup.resource_man.registry.data_ids.script)
         .cloned()
@@ -373,7 +471,7 @@ fn config_script(
     loop_store.gui_state.text_field.searchable_id(
         ui,
         &setup.resource_man,
-        scripts,
+        &scripts,
         &mut new_script,
         TextField::Filter,
         setup.resource_man.translates.gui[&setup.resource_man.registry.gui_ids.hint_search_script]
@@ -395,17 +493,17 @@ fn config_script(
 
     if new_script != current_script {
         if let Some(script) = new_script {
-            tile_entity
-                .send_message(TileEntityMsg::SetDataValue(
-                    setup.resource_man.registry.data_ids.script,
-                    Data::Id(script),
-                ))
-                .unwrap();
-            tile_entity
-                .send_message(TileEntityMsg::RemoveData(
-                    setup.resource_man.registry.data_ids.buffer,
-                ))
-                .unwrap();
+            set_data(
+                setup,
+                coord,
+                vec![
+                    (
+                        setup.resource_man.registry.data_ids.script,
+                        Some(Data::Id(script)),
+                    ),
+                    (setup.resource_man.registry.data_ids.buffer, None),
+                ],
+            );
         }
     }
 }
@@ -452,7 +550,15 @@ pub fn tile_config(
         {
             ui.add_space(MARGIN);
             ui.vertical(|ui| {
-                config_script(ui, setup, loop_store, &data, scripts, entity.clone());
+                config_script(
+                    ui,
+                    setup,
+                    loop_store,
+                    &data,
+                    scripts,
+                    game_data,
+                    config_open_at,
+                );
             });
             ui.add_space(MARGIN);
         }
@@ -470,7 +576,7 @@ pub fn tile_config(
             {
                 ui.add_space(MARGIN);
                 ui.vertical(|ui| {
-                    takeable_item(ui, setup, loop_store, buffer, game_data, entity.clone());
+                    takeable_item(ui, setup, loop_store, buffer, game_data, config_open_at);
                 });
                 ui.add_space(MARGIN);
             }
@@ -489,9 +595,20 @@ pub fn tile_config(
                     loop_store,
                     &data,
                     item_type,
-                    entity.clone(),
+                    config_open_at,
                     tile_info,
                 );
+
+                if is_creative(&setup.resource_man, game_data)
+                    && tile_info
+                        .data
+                        .get(&setup.resource_man.registry.data_ids.storage_takeable)
+                        .cloned()
+                        .and_then(Data::into_bool)
+                        .unwrap_or(false)
+                {
+                    creative_fill(ui, setup, &data, config_open_at);
+                }
             });
             ui.add_space(MARGIN);
         }
@@ -505,7 +622,7 @@ pub fn tile_config(
         {
             ui.add_space(MARGIN);
             ui.vertical(|ui| {
-                config_target(ui, setup, &data, entity.clone());
+                config_target(ui, setup, &data, config_open_at);
             });
             ui.add_space(MARGIN);
         }
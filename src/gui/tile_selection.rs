@@ -16,6 +16,7 @@ use automancy_resources::format;
 
 use crate::event::EventLoopStorage;
 use crate::gui::{GameEguiCallback, LARGE_ICON_SIZE, MEDIUM_ICON_SIZE};
+use crate::map::is_creative;
 use crate::setup::GameSetup;
 
 fn tile_hover_z_angle(ui: &Ui, response: &Response) -> Float {
@@ -29,6 +30,10 @@ fn tile_hover_z_angle(ui: &Ui, response: &Response) -> Float {
 }
 
 fn has_category_item(setup: &GameSetup, id: Id, game_data: &mut DataMap) -> bool {
+    if is_creative(&setup.resource_man, game_data) {
+        return true;
+    }
+
     let category = &setup.resource_man.registry.categories[&id];
 
     if let Some(item) = category.item {
@@ -81,16 +86,8 @@ fn draw_tile_selection(
             _ => false,
         };
 
-        if !is_default_tile {
-            if let Some(research) = setup.resource_man.get_research_by_unlock(*id) {
-                if let Some(Data::SetId(unlocked)) =
-                    game_data.get(&setup.resource_man.registry.data_ids.unlocked_researches)
-                {
-                    if !unlocked.contains(&research.id) {
-                        continue;
-                    }
-                }
-            }
+        if !is_default_tile && !setup.resource_man.is_unlocked(*id, game_data) {
+            continue;
         }
 
         let tile = setup.resource_man.registry.tiles.get(id).unwrap();
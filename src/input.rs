@@ -15,7 +15,16 @@ use crate::options::Options;
 
 pub static DEFAULT_KEYMAP: &[(Key, KeyAction)] = &[
     (Key::Character(SmolStr::new_inline("z")), actions::UNDO),
+    (Key::Character(SmolStr::new_inline("y")), actions::REDO),
+    (Key::Character(SmolStr::new_inline("c")), actions::COPY),
+    (Key::Character(SmolStr::new_inline("v")), actions::PASTE),
+    (Key::Character(SmolStr::new_inline("r")), actions::ROTATE),
+    (Key::Character(SmolStr::new_inline("m")), actions::MIRROR),
     (Key::Character(SmolStr::new_inline("e")), actions::PLAYER),
+    (
+        Key::Character(SmolStr::new_inline("p")),
+        actions::STATISTICS,
+    ),
     (Key::Named(NamedKey::Escape), actions::ESCAPE),
     (Key::Named(NamedKey::F1), actions::HIDE_GUI),
     (Key::Named(NamedKey::F2), actions::SCREENSHOT),
@@ -27,11 +36,17 @@ pub static DEFAULT_KEYMAP: &[(Key, KeyAction)] = &[
 pub enum KeyActions {
     Escape,
     Undo,
+    Redo,
     Debug,
     Fullscreen,
     Screenshot,
     HideGui,
     Player,
+    Statistics,
+    Copy,
+    Paste,
+    Rotate,
+    Mirror,
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -58,6 +73,10 @@ pub mod actions {
         action: KeyActions::Undo,
         press_type: PressTypes::Tap,
     };
+    pub static REDO: KeyAction = KeyAction {
+        action: KeyActions::Redo,
+        press_type: PressTypes::Tap,
+    };
     pub static DEBUG: KeyAction = KeyAction {
         action: KeyActions::Debug,
         press_type: PressTypes::Toggle,
@@ -78,6 +97,26 @@ pub mod actions {
         action: KeyActions::Player,
         press_type: PressTypes::Toggle,
     };
+    pub static STATISTICS: KeyAction = KeyAction {
+        action: KeyActions::Statistics,
+        press_type: PressTypes::Toggle,
+    };
+    pub static COPY: KeyAction = KeyAction {
+        action: KeyActions::Copy,
+        press_type: PressTypes::Tap,
+    };
+    pub static PASTE: KeyAction = KeyAction {
+        action: KeyActions::Paste,
+        press_type: PressTypes::Tap,
+    };
+    pub static ROTATE: KeyAction = KeyAction {
+        action: KeyActions::Rotate,
+        press_type: PressTypes::Tap,
+    };
+    pub static MIRROR: KeyAction = KeyAction {
+        action: KeyActions::Mirror,
+        press_type: PressTypes::Tap,
+    };
 }
 
 /// The various controls of the game.
@@ -3,6 +3,7 @@ pub static LOGO_PATH: &str = "assets/logo.png";
 pub static LOGO: &[u8] = include_bytes!("assets/logo.png");
 pub static SSAO_NOISE_MAP: &[u8] = include_bytes!("assets/noise_map.png");
 
+pub mod blueprint;
 pub mod camera;
 pub mod event;
 pub mod game;
@@ -10,8 +11,11 @@ pub mod gpu;
 pub mod gui;
 pub mod input;
 pub mod map;
+pub mod migration;
 pub mod options;
+pub mod remap;
 pub mod renderer;
 pub mod setup;
+pub mod statistics;
 pub mod tile_entity;
 pub mod util;
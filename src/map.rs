@@ -1,32 +1,62 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
-use std::sync::Arc;
-use std::time::SystemTime;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
 use std::{fs, path::PathBuf};
 
+use anyhow::{anyhow, bail};
 use hashbrown::{HashMap, HashSet};
+use image::imageops::FilterType;
+use image::{ImageOutputFormat, RgbaImage};
 use lazy_static::lazy_static;
+use ractor::rpc::CallResult;
 use ractor::ActorRef;
+use rand::{thread_rng, RngCore};
+use rhai::INT;
 use ron::error::SpannedResult;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
+use walkdir::WalkDir;
 use zstd::{Decoder, Encoder};
 
-use automancy_defs::coord::TileCoord;
-use automancy_defs::id::{Id, IdRaw, Interner};
+use automancy_defs::coord::{TileCoord, TileUnit};
+use automancy_defs::id::{Id, IdRaw};
 use automancy_defs::log;
 use automancy_resources::chrono::Local;
-use automancy_resources::data::{DataMap, DataMapRaw};
+use automancy_resources::data::stack::ItemAmount;
+use automancy_resources::data::{Data, DataMap, DataMapRaw, DataRaw};
 use automancy_resources::ResourceManager;
 
 use crate::game;
 use crate::game::GameMsg;
+use crate::migration::{migrate, CHUNKED_VERSION, MAP_VERSION};
+use crate::remap::{remap_ids, IdRemap};
 use crate::tile_entity::TileEntityMsg;
+use crate::util::actor::multi_call_iter;
 
 pub const MAP_PATH: &str = "map";
 pub const MAP_EXT: &str = ".zst";
 pub const INFO_EXT: &str = ".ron";
+pub const BACKUP_PATH: &str = "backups";
+pub const CHUNK_PATH: &str = "chunks";
+pub const TEMP_EXT: &str = ".tmp";
+pub const OLD_EXT: &str = ".old";
+pub const THUMBNAIL_PATH: &str = "thumbnail.png";
+
+/// The width of a map's thumbnail. The height follows the aspect ratio of the window it was taken in.
+pub const THUMBNAIL_WIDTH: u32 = 256;
+
+/// The width and height, in axial coordinates, of the parallelogram of tiles that is stored in one chunk.
+pub const CHUNK_SIZE: TileUnit = 32;
+
+/// How many autosaves are kept for each map. Once all of them are used, the oldest one is overwritten.
+pub const BACKUP_SLOTS: usize = 5;
 
 pub const MAIN_MENU: &str = ".main_menu";
 
@@ -34,6 +64,30 @@ const MAP_BUFFER_SIZE: usize = 256 * 1024;
 
 pub type Tiles = HashMap<TileCoord, Id>;
 pub type TileEntities = HashMap<TileCoord, ActorRef<TileEntityMsg>>;
+/// The original ID and data of the tiles that were missing from the game when the map was loaded,
+/// by the key stored in the data of the placeholder tiles standing in for them.
+///
+/// The key moves along with the data when a placeholder is moved, rotated, copied, or brought back by undoing.
+pub type Placeholders = HashMap<ItemAmount, (IdRaw, DataMapRaw)>;
+/// The position of a chunk, in chunks. A map's tiles are saved in one file per chunk.
+pub type ChunkCoord = (TileUnit, TileUnit);
+
+/// Gets the chunk a tile is saved in.
+pub fn chunk_of(coord: TileCoord) -> ChunkCoord {
+    (
+        coord.x.div_euclid(CHUNK_SIZE),
+        coord.y.div_euclid(CHUNK_SIZE),
+    )
+}
+
+/// Whether a map is in creative mode, going by its data.
+/// Creative maps place tiles without taking their category items, and let storages be filled with any item.
+pub fn is_creative(resource_man: &ResourceManager, data: &DataMap) -> bool {
+    data.get(&resource_man.registry.data_ids.creative) == Some(&Data::Bool(true))
+}
+
+/// The random seed of a map, from which all the randomness in the map is derived.
+pub type MapSeed = i32;
 
 /// Contains information about a map.
 #[derive(Debug, Clone, Default)]
@@ -42,17 +96,251 @@ pub struct MapInfo {
     pub save_time: Option<SystemTime>,
     /// The map data.
     pub data: DataMap,
+    /// What the player wrote about the map.
+    pub description: String,
+    /// How long the map has been played for.
+    pub play_time: Duration,
+    /// How many ticks the map has been run for, which the randomness of its tiles is derived from.
+    pub tick_count: u64,
+}
+
+impl MapInfo {
+    /// Gets the random seed of the map, generating and storing a new one if there is none.
+    pub fn seed(&mut self, resource_man: &ResourceManager) -> MapSeed {
+        let seed = self
+            .data
+            .entry(resource_man.registry.data_ids.seed)
+            .or_insert_with(|| Data::Amount(thread_rng().next_u32() as MapSeed));
+
+        if let Data::Amount(seed) = seed {
+            *seed
+        } else {
+            log::warn!("Map seed is not an amount, replacing it with a new one");
+
+            let new = thread_rng().next_u32() as MapSeed;
+            *seed = Data::Amount(new);
+
+            new
+        }
+    }
+}
+
+/// How the tiles of a map are encoded before they are compressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapCodec {
+    /// Human-readable RON.
+    #[default]
+    Ron,
+    /// Compact binary, which is a lot faster to read and write.
+    Bincode,
+}
+
+/// How many of each tile a map has, and how many of each item its tiles hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MapSummary {
+    pub tiles: BTreeMap<IdRaw, u64>,
+    pub items: BTreeMap<IdRaw, u64>,
+}
+
+impl MapSummary {
+    /// Counts the tiles of a MapRaw, and the items in their inventories, into the summary.
+    pub fn add(&mut self, map: &MapRaw) {
+        for (_, id, data) in &map.tiles {
+            if let Some(raw_id) = map.tile_map.get(id) {
+                *self.tiles.entry(raw_id.clone()).or_default() += 1;
+            }
+
+            for value in data.values() {
+                if let DataRaw::Inventory(inventory) = value {
+                    for (item, amount) in inventory.iter() {
+                        *self.items.entry(item.clone()).or_default() += (*amount).max(0) as u64;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds the counts of another summary into this one.
+    pub fn merge(&mut self, other: &MapSummary) {
+        for (id, count) in &other.tiles {
+            *self.tiles.entry(id.clone()).or_default() += count;
+        }
+
+        for (id, count) in &other.items {
+            *self.items.entry(id.clone()).or_default() += count;
+        }
+    }
+}
+
+/// A chunk as it is on disk, so that it does not need to be read or written again while it stays the same.
+#[derive(Debug, Clone, Default)]
+pub struct WrittenChunk {
+    /// The hash of the tiles in the chunk.
+    pub hash: u64,
+    /// What the tiles in the chunk are made of.
+    pub summary: MapSummary,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MapInfoRaw {
+    /// The version of the format the map was saved in. Maps saved before versioning are version 0.
+    #[serde(default)]
+    pub version: u32,
     /// The number of saved tiles.
     #[serde(default)]
     pub tile_count: u64,
+    /// How the tiles are encoded. Maps saved before `CODEC_VERSION` have none, and are RON.
+    #[serde(default)]
+    pub codec: MapCodec,
+    /// What the player wrote about the map.
+    #[serde(default)]
+    pub description: String,
+    /// How long the map has been played for, in seconds.
+    #[serde(default)]
+    pub play_time: u64,
+    /// How many ticks the map has been run for.
+    #[serde(default)]
+    pub tick_count: u64,
+    /// What the saved tiles are made of, so that it can be shown without reading them.
+    #[serde(default)]
+    pub summary: MapSummary,
     #[serde(default)]
     pub data: DataMapRaw,
 }
 
+/// The tiles and the header of a map as they were at one moment, in their raw form.
+///
+/// Writing a snapshot only touches the disk, so it can be done without holding up the game.
+pub struct MapSnapshot {
+    map_name: String,
+    /// the chunks that are written
+    chunks: HashMap<ChunkCoord, MapRaw>,
+    /// the chunks whose files are kept as they are
+    kept: HashMap<ChunkCoord, WrittenChunk>,
+    info: MapInfoRaw,
+}
+
+impl MapSnapshot {
+    /// Writes the snapshot into a directory, reusing the files of the kept chunks and the chunks whose hashes match
+    /// the ones already written. Returns all the chunks as they are written.
+    ///
+    /// The chunks are staged next to the ones in the directory and swapped in with the header,
+    /// so that a failed write leaves the directory as it was.
+    pub fn write(
+        mut self,
+        dir: &Path,
+        written: &HashMap<ChunkCoord, WrittenChunk>,
+    ) -> anyhow::Result<HashMap<ChunkCoord, WrittenChunk>> {
+        let staged = Map::temp_path(&dir.join(CHUNK_PATH));
+
+        drop(fs::remove_dir_all(&staged));
+        fs::create_dir_all(&staged)?;
+
+        let chunks = match self.stage_chunks(dir, &staged, written) {
+            Ok(v) => v,
+            Err(e) => {
+                drop(fs::remove_dir_all(&staged));
+
+                return Err(e);
+            }
+        };
+
+        // the header goes last, as it decides how the tiles are read
+        Map::swap_in_chunks(dir, &staged, || {
+            Map::write_atomically(&Map::info_in(dir), |mut writer| {
+                ron::ser::to_writer(&mut writer, &self.info)?;
+
+                Ok(writer)
+            })
+        })?;
+
+        Ok(chunks)
+    }
+
+    /// Writes the snapshot into the next backup slot of its map.
+    ///
+    /// The backup is written into a folder next to the slot first, and only replaces the slot once it is complete,
+    /// so that a failed autosave leaves the old backup in the slot.
+    pub fn write_backup(self) -> anyhow::Result<()> {
+        let slot = Map::backup(&self.map_name, Map::next_backup_slot(&self.map_name));
+        let temp = Map::temp_path(&slot);
+
+        // the backup is written from scratch
+        drop(fs::remove_dir_all(&temp));
+
+        if let Err(e) = self.write(&temp, &HashMap::new()) {
+            drop(fs::remove_dir_all(&temp));
+
+            return Err(e);
+        }
+
+        let old = Map::old_path(&slot);
+
+        drop(fs::remove_dir_all(&old));
+        if slot.exists() {
+            fs::rename(&slot, &old)?;
+        }
+
+        if let Err(e) = fs::rename(&temp, &slot) {
+            drop(fs::rename(&old, &slot));
+
+            return Err(e.into());
+        }
+
+        drop(fs::remove_dir_all(&old));
+
+        Ok(())
+    }
+
+    /// Writes every chunk into `staged`, linking to the file in `dir` for the chunks that did not change,
+    /// and sums up the tiles in them into the header.
+    /// Returns all the chunks as they are written.
+    fn stage_chunks(
+        &mut self,
+        dir: &Path,
+        staged: &Path,
+        written: &HashMap<ChunkCoord, WrittenChunk>,
+    ) -> anyhow::Result<HashMap<ChunkCoord, WrittenChunk>> {
+        let link = |chunk: ChunkCoord| {
+            let path = staged.join(Map::chunk_file(chunk));
+            let kept = Map::chunk_in(dir, chunk);
+
+            // the file is copied if it cannot be linked
+            fs::hard_link(&kept, &path).or_else(|_| fs::copy(&kept, &path).map(drop))
+        };
+
+        let mut chunks = HashMap::new();
+
+        for (chunk, kept) in mem::take(&mut self.kept) {
+            link(chunk).map_err(|e| anyhow!("could not keep chunk {chunk:?}: {e}"))?;
+
+            self.info.summary.merge(&kept.summary);
+            chunks.insert(chunk, kept);
+        }
+
+        for (chunk, map_raw) in &self.chunks {
+            let mut summary = MapSummary::default();
+            summary.add(map_raw);
+            self.info.summary.merge(&summary);
+
+            let hash = map_raw.content_hash();
+
+            // a chunk that ended up the same as on disk keeps its file
+            let reused = written.get(chunk).map(|v| v.hash) == Some(hash) && link(*chunk).is_ok();
+
+            if !reused {
+                let path = staged.join(Map::chunk_file(*chunk));
+
+                Map::write_atomically(&path, |writer| map_raw.encode(writer, self.info.codec))?;
+            }
+
+            chunks.insert(*chunk, WrittenChunk { hash, summary });
+        }
+
+        Ok(chunks)
+    }
+}
+
 /// A map stores tiles and tile entities to disk.
 #[derive(Debug, Clone)]
 pub struct Map {
@@ -62,16 +350,141 @@ pub struct Map {
     pub tiles: Tiles,
     /// The map's info.
     pub info: Arc<Mutex<MapInfo>>,
+    /// The tiles that are kept as placeholders, which are written back as they were when saving.
+    pub placeholders: Placeholders,
+    /// The chunks as they are on disk, so that only the chunks that changed are written.
+    pub written_chunks: HashMap<ChunkCoord, WrittenChunk>,
+    /// The chunks whose tiles were placed, removed, or had their data changed since they were last written.
+    /// Only the tiles in these chunks are asked for their data when saving.
+    pub dirty_chunks: HashSet<ChunkCoord>,
+    /// How the tiles are encoded on disk.
+    pub codec: MapCodec,
 }
 
 /// A map stores tiles and tile entities to disk.
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// Each chunk of a map is saved as its own MapRaw, with only the tiles in the chunk.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MapRaw {
     pub map_name: String,
     pub tiles: Vec<(TileCoord, Id, DataMapRaw)>,
     pub tile_map: HashMap<Id, IdRaw>,
 }
 
+impl MapRaw {
+    /// Decodes zstd compressed tiles.
+    pub fn decode(reader: impl Read, codec: MapCodec) -> anyhow::Result<Self> {
+        let decoder = Decoder::new(reader)?;
+
+        Ok(match codec {
+            MapCodec::Ron => ron::de::from_reader(decoder)?,
+            MapCodec::Bincode => bincode::deserialize_from(decoder)?,
+        })
+    }
+
+    /// Encodes the tiles and compresses them with zstd, returning the writer once everything is written.
+    pub fn encode<W: Write>(&self, writer: W, codec: MapCodec) -> anyhow::Result<W> {
+        let mut encoder = Encoder::new(writer, 0)?;
+
+        match codec {
+            MapCodec::Ron => ron::ser::to_writer(&mut encoder, self)?,
+            MapCodec::Bincode => bincode::serialize_into(&mut encoder, self)?,
+        }
+
+        Ok(encoder.finish()?)
+    }
+
+    /// Splits the tiles into the chunks they are saved in, each with the part of the tile map that it uses.
+    pub fn into_chunks(self) -> HashMap<ChunkCoord, MapRaw> {
+        let mut chunks = HashMap::<ChunkCoord, MapRaw>::new();
+
+        for (coord, id, data) in self.tiles {
+            let chunk = chunks.entry(chunk_of(coord)).or_insert_with(|| MapRaw {
+                map_name: self.map_name.clone(),
+                ..Default::default()
+            });
+
+            if let Some(raw_id) = self.tile_map.get(&id) {
+                chunk.tile_map.insert(id, raw_id.clone());
+            }
+
+            chunk.tiles.push((coord, id, data));
+        }
+
+        chunks
+    }
+
+    /// Joins chunks back into one MapRaw. Chunks may save the same tile under different keys,
+    /// so the tiles are given new keys.
+    pub fn join(map_name: String, chunks: impl IntoIterator<Item = MapRaw>) -> MapRaw {
+        let mut keys = HashMap::<IdRaw, Id>::new();
+        let mut tiles = Vec::new();
+
+        for chunk in chunks {
+            let MapRaw {
+                tiles: chunk_tiles,
+                tile_map,
+                ..
+            } = chunk;
+
+            for (coord, id, data) in chunk_tiles {
+                let Some(raw_id) = tile_map.get(&id) else {
+                    continue;
+                };
+
+                let len = keys.len();
+                let id = *keys
+                    .entry(raw_id.clone())
+                    .or_insert_with(|| Id::from(len as INT));
+
+                tiles.push((coord, id, data));
+            }
+        }
+
+        MapRaw {
+            map_name,
+            tiles,
+            tile_map: keys.into_iter().map(|(raw_id, id)| (id, raw_id)).collect(),
+        }
+    }
+
+    /// Hashes the tiles in a way that does not depend on their order, the order of their data,
+    /// or the keys their IDs are saved under, so that a chunk that did not change keeps its hash.
+    pub fn content_hash(&self) -> u64 {
+        fn canonical(value: &DataRaw) -> DataRaw {
+            let mut value = value.clone();
+
+            match &mut value {
+                DataRaw::Inventory(inventory) => inventory.sort(),
+                DataRaw::SetId(ids) => ids.sort(),
+                _ => {}
+            }
+
+            value
+        }
+
+        let mut tiles = self
+            .tiles
+            .iter()
+            .map(|(coord, id, data)| {
+                let data = data
+                    .iter()
+                    .map(|(key, value)| (key, canonical(value)))
+                    .collect::<BTreeMap<_, _>>();
+
+                (*coord, self.tile_map.get(id), data)
+            })
+            .collect::<Vec<_>>();
+
+        tiles.sort_by_key(|(coord, ..)| (coord.x, coord.y));
+
+        let mut hasher = DefaultHasher::new();
+        tiles.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
 impl Map {
     /// Creates a new empty map.
     pub fn new_empty(map_name: String) -> Self {
@@ -79,22 +492,269 @@ impl Map {
             map_name,
             tiles: Default::default(),
             info: Arc::new(Default::default()),
+            placeholders: Default::default(),
+            written_chunks: Default::default(),
+            dirty_chunks: Default::default(),
+            codec: Default::default(),
         }
     }
 
+    /// Keeps a tile that is missing from the game, returning the data of the placeholder tile that stands in for it.
+    pub fn add_placeholder(
+        placeholders: &mut Placeholders,
+        resource_man: &ResourceManager,
+        original: (IdRaw, DataMapRaw),
+    ) -> DataMap {
+        let key = placeholders.len() as ItemAmount;
+        placeholders.insert(key, original);
+
+        let mut data = DataMap::default();
+        data.insert(
+            resource_man.registry.data_ids.placeholder_key,
+            Data::Amount(key),
+        );
+
+        data
+    }
+
+    /// Gets the original ID and data of the tile a placeholder tile stands in for.
+    pub fn placeholder_of(
+        &self,
+        resource_man: &ResourceManager,
+        id: Id,
+        data: &DataMap,
+    ) -> Option<&(IdRaw, DataMapRaw)> {
+        if id != resource_man.registry.placeholder {
+            return None;
+        }
+
+        match data.get(&resource_man.registry.data_ids.placeholder_key) {
+            Some(Data::Amount(key)) => self.placeholders.get(key),
+            _ => None,
+        }
+    }
+
+    /// Gets the folder the maps are in.
+    pub fn root() -> PathBuf {
+        MAP_ROOT.read().unwrap().clone()
+    }
+
+    /// Sets the folder the maps are in, for when they should not be in `MAP_PATH` under the working directory.
+    pub fn set_root(root: impl Into<PathBuf>) {
+        *MAP_ROOT.write().unwrap() = root.into();
+    }
+
     /// Gets the path to a map from its name.
     pub fn path(map_name: &str) -> PathBuf {
-        PathBuf::from(format!("{MAP_PATH}/{map_name}/"))
+        Map::root().join(map_name)
     }
 
     /// Gets the path to a map's header from its name.
     pub fn info(map_name: &str) -> PathBuf {
-        Map::path(map_name).join(format!("header{INFO_EXT}"))
+        Map::info_in(&Map::path(map_name))
     }
 
-    /// Gets the path to a map's tiles from its name.
+    /// Gets the path to a map's tiles from its name, for maps saved before chunks were introduced.
     pub fn map(map_name: &str) -> PathBuf {
-        Map::path(map_name).join(format!("map{MAP_EXT}"))
+        Map::map_in(&Map::path(map_name))
+    }
+
+    /// Gets the path to a map's chunks from its name.
+    pub fn chunks(map_name: &str) -> PathBuf {
+        Map::path(map_name).join(CHUNK_PATH)
+    }
+
+    /// Gets the path to a map's thumbnail from its name.
+    pub fn thumbnail(map_name: &str) -> PathBuf {
+        Map::path(map_name).join(THUMBNAIL_PATH)
+    }
+
+    fn info_in(dir: &Path) -> PathBuf {
+        dir.join(format!("header{INFO_EXT}"))
+    }
+
+    fn map_in(dir: &Path) -> PathBuf {
+        dir.join(format!("map{MAP_EXT}"))
+    }
+
+    fn old_chunks_in(dir: &Path) -> PathBuf {
+        Map::old_path(&dir.join(CHUNK_PATH))
+    }
+
+    fn chunk_file((q, r): ChunkCoord) -> String {
+        format!("{q}_{r}{MAP_EXT}")
+    }
+
+    fn chunk_in(dir: &Path, chunk: ChunkCoord) -> PathBuf {
+        dir.join(CHUNK_PATH).join(Map::chunk_file(chunk))
+    }
+
+    /// Gets the chunk a chunk file is for, or None if it is not a chunk file.
+    fn chunk_from_path(path: &Path) -> Option<ChunkCoord> {
+        let name = path.file_name()?.to_str()?.strip_suffix(MAP_EXT)?;
+        let (q, r) = name.split_once('_')?;
+
+        Some((q.parse().ok()?, r.parse().ok()?))
+    }
+
+    /// Gets the files a map's tiles are stored in. Maps saved before chunks were introduced keep every tile in one file.
+    pub fn tile_files(map_name: &str, version: u32) -> Vec<PathBuf> {
+        Map::recover_chunks(&Map::path(map_name));
+
+        if version < CHUNKED_VERSION {
+            return vec![Map::map(map_name)];
+        }
+
+        let Ok(dir) = fs::read_dir(Map::chunks(map_name)) else {
+            return vec![];
+        };
+
+        dir.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| Map::chunk_from_path(path).is_some())
+            .collect()
+    }
+
+    /// Gets the path to a map's backups from its name.
+    pub fn backups(map_name: &str) -> PathBuf {
+        Map::path(map_name).join(BACKUP_PATH)
+    }
+
+    /// Gets the path to one of a map's backup slots.
+    pub fn backup(map_name: &str, slot: usize) -> PathBuf {
+        Map::backups(map_name).join(slot.to_string())
+    }
+
+    /// Lists the backup slots of a map that are in use along with their save time, newest first.
+    pub fn list_backups(map_name: &str) -> Vec<(usize, SystemTime)> {
+        let mut backups = (0..BACKUP_SLOTS)
+            .flat_map(|slot| {
+                let time = fs::metadata(Map::info_in(&Map::backup(map_name, slot)))
+                    .and_then(|v| v.modified())
+                    .ok()?;
+
+                Some((slot, time))
+            })
+            .collect::<Vec<_>>();
+
+        backups.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        backups
+    }
+
+    /// Gets the backup slot the next autosave goes to, which is the first unused one, or else the oldest one.
+    pub fn next_backup_slot(map_name: &str) -> usize {
+        let backups = Map::list_backups(map_name);
+
+        (0..BACKUP_SLOTS)
+            .find(|slot| !backups.iter().any(|(used, _)| used == slot))
+            .or_else(|| backups.last().map(|(slot, _)| *slot))
+            .unwrap_or(0)
+    }
+
+    /// Overwrites a map with one of its backups.
+    ///
+    /// The chunks of the backup are copied next to the map's chunks first, so that a failed copy leaves the map as it was.
+    pub fn restore_backup(map_name: &str, slot: usize) -> anyhow::Result<()> {
+        let backup = Map::backup(map_name, slot);
+        let dir = Map::path(map_name);
+
+        let staged = Map::temp_path(&dir.join(CHUNK_PATH));
+        drop(fs::remove_dir_all(&staged));
+        fs::create_dir_all(&staged)?;
+
+        let copied = fs::read_dir(backup.join(CHUNK_PATH)).and_then(|chunks| {
+            for entry in chunks {
+                let entry = entry?;
+
+                fs::copy(entry.path(), staged.join(entry.file_name()))?;
+            }
+
+            Ok(())
+        });
+
+        if let Err(e) = copied {
+            drop(fs::remove_dir_all(&staged));
+
+            return Err(e.into());
+        }
+
+        Map::swap_in_chunks(&dir, &staged, || {
+            let temp = Map::temp_path(&Map::info_in(&dir));
+
+            fs::copy(Map::info_in(&backup), &temp)?;
+            fs::rename(&temp, Map::info_in(&dir))?;
+
+            Ok(())
+        })?;
+
+        log::info!("Restored map {map_name} from backup {slot}");
+
+        Ok(())
+    }
+
+    /// Renames a map, along with its backups. Fails with `AlreadyExists` if there is a map with the new name.
+    pub fn rename(map_name: &str, new_name: &str) -> io::Result<()> {
+        let to = Map::path(new_name);
+
+        if to.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("map {new_name} already exists"),
+            ));
+        }
+
+        fs::rename(Map::path(map_name), to)?;
+
+        log::info!("Renamed map {map_name} to {new_name}");
+
+        Ok(())
+    }
+
+    /// Copies a map, along with its backups, under a new name. Fails with `AlreadyExists` if there is a map with the new name.
+    ///
+    /// The copy is made in a hidden folder first, so that a failed copy never shows up as a map.
+    pub fn duplicate(map_name: &str, new_name: &str) -> io::Result<()> {
+        let to = Map::path(new_name);
+
+        if to.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("map {new_name} already exists"),
+            ));
+        }
+
+        let from = Map::path(map_name);
+        let temp = Map::path(&format!(".{new_name}{TEMP_EXT}"));
+
+        drop(fs::remove_dir_all(&temp));
+
+        let copied = WalkDir::new(&from).into_iter().try_for_each(|entry| {
+            let entry = entry?;
+
+            let Ok(relative) = entry.path().strip_prefix(&from) else {
+                return Ok(());
+            };
+            let target = temp.join(relative);
+
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(target)
+            } else {
+                fs::copy(entry.path(), target).map(|_| ())
+            }
+        });
+
+        if let Err(e) = copied {
+            drop(fs::remove_dir_all(&temp));
+
+            return Err(e);
+        }
+
+        fs::rename(&temp, to)?;
+
+        log::info!("Duplicated map {map_name} as {new_name}");
+
+        Ok(())
     }
 
     pub fn read_info(
@@ -137,63 +797,140 @@ impl Map {
         }
     }
 
-    pub fn read_map(resource_man: &ResourceManager, map_name: &str) -> Option<MapRaw> {
-        let path = Self::map(map_name);
+    /// Reads the tiles in one of the files of a map.
+    pub fn read_tiles(path: &Path, codec: MapCodec) -> anyhow::Result<MapRaw> {
+        let file = File::open(path)?;
 
-        let file = File::open(path).ok()?;
-        let decoder = Decoder::new(file).unwrap();
+        MapRaw::decode(file, codec).map_err(|e| e.context(format!("could not read {path:?}")))
+    }
 
-        let decoded: SpannedResult<MapRaw> = ron::de::from_reader(decoder);
+    /// Reports that a map could not be read or migrated.
+    /// The map is left as is on disk, and the session is written somewhere else.
+    fn load_failed(
+        resource_man: &ResourceManager,
+        map_name: &str,
+        version: u32,
+        e: anyhow::Error,
+    ) -> Self {
+        log::error!("Could not load map {map_name}: {e:?}");
 
-        match decoded {
-            Ok(v) => Some(v),
-            Err(e) => {
-                log::error!("Serde: {e:?}");
+        let err_map_name = format!("{}-ERR-{}", map_name, Local::now().format("%y%m%d%H%M%S"));
 
-                let err_map_name =
-                    format!("{}-ERR-{}", map_name, Local::now().format("%y%m%d%H%M%S"));
+        let err = if version > MAP_VERSION {
+            resource_man.registry.err_ids.unsupported_map_version
+        } else {
+            resource_man.registry.err_ids.invalid_map_data
+        };
 
-                resource_man.error_man.push(
-                    (
-                        resource_man.registry.err_ids.invalid_map_data,
-                        vec![map_name.to_string(), err_map_name],
-                    ),
-                    resource_man,
-                );
+        resource_man.error_man.push(
+            (err, vec![map_name.to_string(), err_map_name.clone()]),
+            resource_man,
+        );
 
-                None
-            }
-        }
+        Map::new_empty(err_map_name)
     }
 
-    /// Loads a map from disk.
+    /// Loads a map from disk, replacing the IDs that are missing from the game according to the remap.
+    ///
+    /// Every chunk is read when the map is loaded, as every tile runs each tick, but only one chunk is decoded at a time.
+    /// The chunks are not loaded lazily, as a tile that is not loaded would not run.
     pub async fn load(
         game: ActorRef<GameMsg>,
         resource_man: Arc<ResourceManager>,
         map_name: &str,
+        remap: &IdRemap,
     ) -> (Self, TileEntities) {
-        let Some((info, save_time)) = Map::read_info(&resource_man, map_name) else {
+        let Some((mut info, save_time)) = Map::read_info(&resource_man, map_name) else {
             return (Map::new_empty(map_name.to_string()), Default::default());
         };
 
-        let Some(map) = Map::read_map(&resource_man, map_name) else {
-            return (Map::new_empty(map_name.to_string()), Default::default());
+        // the chunks are migrated on their own, from the version they were saved in
+        let saved = info.clone();
+
+        if let Err(e) = migrate(&mut info, &mut MapRaw::default()) {
+            return (
+                Map::load_failed(&resource_man, map_name, saved.version, e),
+                Default::default(),
+            );
+        }
+
+        remap_ids(&mut info, &mut MapRaw::default(), remap);
+
+        let mut info = MapInfo {
+            save_time,
+            data: info.data.to_data(&resource_man.interner),
+            description: info.description,
+            play_time: Duration::from_secs(info.play_time),
+            tick_count: info.tick_count,
         };
+        let seed = info.seed(&resource_man);
 
         let mut tiles = HashMap::new();
-        let mut tile_entities = HashMap::new();
+        let mut tile_entities: TileEntities = HashMap::new();
+        let mut placeholders = HashMap::new();
+        let mut written_chunks = HashMap::new();
+
+        for path in Map::tile_files(map_name, saved.version) {
+            let read = Map::read_tiles(&path, saved.codec).and_then(|mut map| {
+                let hash = map.content_hash();
+
+                migrate(&mut saved.clone(), &mut map)?;
+
+                Ok((map, hash))
+            });
+
+            // a chunk that cannot be read fails the whole map, so that saving it cannot drop the chunk
+            let (mut map, hash) = match read {
+                Ok(v) => v,
+                Err(e) => {
+                    for tile_entity in tile_entities.values() {
+                        tile_entity.stop(None);
+                    }
+
+                    return (
+                        Map::load_failed(&resource_man, map_name, saved.version, e),
+                        Default::default(),
+                    );
+                }
+            };
+
+            remap_ids(&mut saved.clone(), &mut map, remap);
+
+            // a chunk that is the same as on disk is not written again until it changes
+            if saved.version >= CHUNKED_VERSION && map.content_hash() == hash {
+                if let Some((coord, ..)) = map.tiles.first() {
+                    let mut summary = MapSummary::default();
+                    summary.add(&map);
+
+                    written_chunks.insert(chunk_of(*coord), WrittenChunk { hash, summary });
+                }
+            }
+
+            for (coord, id, data) in map.tiles.into_iter() {
+                let Some(raw_id) = map.tile_map.get(&id) else {
+                    continue;
+                };
+
+                let (id, data) = match resource_man
+                    .interner
+                    .get(raw_id.to_string())
+                    .filter(|id| resource_man.registry.tiles.contains_key(id))
+                {
+                    Some(id) => (id, data.to_data(&resource_man.interner)),
+                    None => (
+                        resource_man.registry.placeholder,
+                        Map::add_placeholder(
+                            &mut placeholders,
+                            &resource_man,
+                            (raw_id.clone(), data),
+                        ),
+                    ),
+                };
 
-        for (coord, id, data) in map.tiles.into_iter() {
-            if let Some(id) = map
-                .tile_map
-                .get(&id)
-                .and_then(|id| resource_man.interner.get(id.to_string()))
-            {
                 let tile_entity =
-                    game::new_tile(resource_man.clone(), game.clone(), coord, id).await;
-                let data = data.to_data(&resource_man.interner).into_inner();
+                    game::new_tile(resource_man.clone(), game.clone(), coord, id, seed).await;
 
-                for (key, value) in data {
+                for (key, value) in data.into_inner() {
                     tile_entity
                         .send_message(TileEntityMsg::SetDataValue(key, value))
                         .unwrap();
@@ -208,68 +945,422 @@ impl Map {
             Self {
                 map_name: map_name.to_string(),
                 tiles,
-                info: Arc::new(Mutex::new(MapInfo {
-                    save_time,
-                    data: info.data.to_data(&resource_man.interner),
-                })),
+                info: Arc::new(Mutex::new(info)),
+                placeholders,
+                written_chunks,
+                dirty_chunks: Default::default(),
+                codec: saved.codec,
             },
             tile_entities,
         )
     }
 
-    /// Saves a map to disk.
-    pub async fn save(&self, interner: &Interner, tile_entities: &TileEntities) {
-        drop(fs::create_dir_all(Map::path(&self.map_name)));
+    /// Saves a map to disk, writing only the chunks that changed since the last save.
+    /// If this fails, each file that was not written yet is left as it was.
+    pub async fn save(
+        &mut self,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+    ) -> anyhow::Result<()> {
+        // the chunks that are the same as on disk keep their files, without asking their tiles for their data
+        let kept = self
+            .written_chunks
+            .iter()
+            .filter(|(chunk, _)| !self.dirty_chunks.contains(*chunk))
+            .map(|(chunk, written)| (*chunk, written.clone()))
+            .collect();
 
-        let info = Self::info(&self.map_name);
-        let info = File::create(info).unwrap();
+        let snapshot = self.snapshot(resource_man, tile_entities, kept).await?;
 
-        let mut info_writer = BufWriter::with_capacity(MAP_BUFFER_SIZE, info);
+        match snapshot.write(&Map::path(&self.map_name), &self.written_chunks) {
+            Ok(written) => {
+                self.written_chunks = written;
+                self.dirty_chunks.clear();
 
-        let tiles = Self::map(&self.map_name);
-        let tiles = File::create(tiles).unwrap();
+                Ok(())
+            }
+            Err(e) => {
+                // the files on disk might not be the ones that were kept, so the next save writes every chunk
+                self.written_chunks.clear();
 
-        let tiles_writer = BufWriter::with_capacity(MAP_BUFFER_SIZE, tiles);
-        let mut tiles_encoder = Encoder::new(tiles_writer, 0).unwrap();
+                Err(e)
+            }
+        }
+    }
 
-        let mut map_raw = MapRaw {
-            map_name: self.map_name.clone(),
-            tiles: vec![],
-            tile_map: Default::default(),
+    /// Takes a snapshot of the map to write into its next backup slot with [`MapSnapshot::write_backup`].
+    pub async fn snapshot_backup(
+        &self,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+    ) -> anyhow::Result<MapSnapshot> {
+        self.snapshot(resource_man, tile_entities, HashMap::new())
+            .await
+    }
+
+    /// Moves the map over to newly loaded resources, whose IDs may not match the old ones.
+    ///
+    /// The tile entities are stopped and made again with the new resources, with their data carried over through its raw form.
+    /// The tiles that no longer exist become placeholders, and the placeholders that exist again become tiles.
+    pub async fn reload(
+        &mut self,
+        game: ActorRef<GameMsg>,
+        old_resource_man: &ResourceManager,
+        resource_man: Arc<ResourceManager>,
+        old_tile_entities: TileEntities,
+    ) -> TileEntities {
+        let seed = {
+            let mut info = self.info.lock().await;
+
+            info.data = info
+                .data
+                .to_raw(&old_resource_man.interner)
+                .to_data(&resource_man.interner);
+
+            info.seed(&resource_man)
         };
 
+        let mut tiles = HashMap::new();
+        let mut tile_entities: TileEntities = HashMap::new();
+        let mut placeholders = HashMap::new();
+
+        for (coord, id) in mem::take(&mut self.tiles) {
+            let Some(tile_entity) = old_tile_entities.get(&coord) else {
+                continue;
+            };
+
+            let data = match tile_entity.call(TileEntityMsg::TakeData, None).await {
+                Ok(CallResult::Success(data)) => data,
+                _ => DataMap::default(),
+            };
+
+            let (raw_id, data) = match self.placeholder_of(old_resource_man, id, &data) {
+                Some(original) => original.clone(),
+                None => (
+                    IdRaw::parse(old_resource_man.interner.resolve(id).unwrap()),
+                    data.to_raw(&old_resource_man.interner),
+                ),
+            };
+
+            let (id, data) = match resource_man
+                .interner
+                .get(raw_id.to_string())
+                .filter(|id| resource_man.registry.tiles.contains_key(id))
+            {
+                Some(id) => (id, data.to_data(&resource_man.interner)),
+                None => (
+                    resource_man.registry.placeholder,
+                    Map::add_placeholder(&mut placeholders, &resource_man, (raw_id, data)),
+                ),
+            };
+
+            let tile_entity =
+                game::new_tile(resource_man.clone(), game.clone(), coord, id, seed).await;
+
+            tile_entity
+                .send_message(TileEntityMsg::SetData(data))
+                .unwrap();
+
+            tiles.insert(coord, id);
+            tile_entities.insert(coord, tile_entity);
+        }
+
+        for tile_entity in old_tile_entities.values() {
+            tile_entity.stop(Some("Reloading resources".to_string()));
+        }
+
+        self.tiles = tiles;
+        self.placeholders = placeholders;
+        // the tiles that were placeholders are saved as themselves again
+        self.dirty_chunks
+            .extend(self.tiles.keys().map(|coord| chunk_of(*coord)));
+
+        tile_entities
+    }
+
+    /// Gets the path a file is written to before it is moved into place.
+    fn temp_path(path: &Path) -> PathBuf {
+        let mut path = path.as_os_str().to_owned();
+        path.push(TEMP_EXT);
+
+        PathBuf::from(path)
+    }
+
+    /// Gets the path something is moved to while what replaces it is moved into place.
+    fn old_path(path: &Path) -> PathBuf {
+        let mut path = path.as_os_str().to_owned();
+        path.push(OLD_EXT);
+
+        PathBuf::from(path)
+    }
+
+    /// Writes a file next to where it goes first, and only then moves it over the old one,
+    /// so that a crash or a full disk never leaves a half-written file behind.
+    fn write_atomically(
+        path: &Path,
+        write: impl FnOnce(BufWriter<File>) -> anyhow::Result<BufWriter<File>>,
+    ) -> anyhow::Result<()> {
+        let temp = Self::temp_path(path);
+
+        let written = File::create(&temp)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| write(BufWriter::with_capacity(MAP_BUFFER_SIZE, file)))
+            .and_then(|writer| Ok(writer.into_inner()?.sync_all()?));
+
+        if let Err(e) = written {
+            drop(fs::remove_file(&temp));
+
+            return Err(e);
+        }
+
+        fs::rename(&temp, path)?;
+
+        Ok(())
+    }
+
+    /// Swaps the chunks staged in `staged` in for the chunks in `dir`, and then writes the header that goes with them.
+    ///
+    /// Each step is a rename, and if the header cannot be written the old chunks are put back.
+    /// If the game stops between the steps, the old chunks are put back when the map is next read.
+    fn swap_in_chunks(
+        dir: &Path,
+        staged: &Path,
+        write_header: impl FnOnce() -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let chunks = dir.join(CHUNK_PATH);
+        let old = Map::old_chunks_in(dir);
+
+        drop(fs::remove_dir_all(&old));
+        if chunks.exists() {
+            fs::rename(&chunks, &old)?;
+        }
+
+        let swapped = fs::rename(staged, &chunks)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| write_header());
+
+        if let Err(e) = swapped {
+            drop(fs::remove_dir_all(staged));
+            if old.exists() {
+                drop(fs::remove_dir_all(&chunks));
+                drop(fs::rename(&old, &chunks));
+            }
+
+            return Err(e);
+        }
+
+        drop(fs::remove_dir_all(&old));
+        // maps saved before chunks were introduced have their tiles in chunks now
+        drop(fs::remove_file(Map::map_in(dir)));
+
+        Ok(())
+    }
+
+    /// Puts back the chunks of a map whose chunks were being swapped out when the game stopped.
+    /// The header is only written once the new chunks are in place, so it still goes with the old ones.
+    fn recover_chunks(dir: &Path) {
+        let chunks = dir.join(CHUNK_PATH);
+        let old = Map::old_chunks_in(dir);
+
+        if !chunks.exists() && old.exists() {
+            log::warn!("Putting back the chunks of {dir:?}, which were being replaced when the game stopped");
+
+            drop(fs::rename(old, chunks));
+        }
+    }
+
+    /// Takes the tiles and the header of the map as they are now, asking the tiles of each chunk for their data at once.
+    /// The tiles in the kept chunks are left out, as the files of those chunks are kept.
+    async fn snapshot(
+        &self,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+        mut kept: HashMap<ChunkCoord, WrittenChunk>,
+    ) -> anyhow::Result<MapSnapshot> {
+        let interner = &resource_man.interner;
+
+        let mut tiles = HashMap::<ChunkCoord, Vec<(TileCoord, Id)>>::new();
+
         for (coord, id) in self.tiles.iter() {
-            if let Some(tile_entity) = tile_entities.get(coord) {
-                if !map_raw.tile_map.contains_key(id) {
-                    map_raw
-                        .tile_map
-                        .insert(*id, IdRaw::parse(interner.resolve(*id).unwrap()));
+            tiles
+                .entry(chunk_of(*coord))
+                .or_default()
+                .push((*coord, *id));
+        }
+
+        // a chunk whose tiles are all gone has no file to keep
+        kept.retain(|chunk, _| tiles.contains_key(chunk));
+        tiles.retain(|chunk, _| !kept.contains_key(chunk));
+
+        // placeholders are saved under keys past the interned IDs, so that they cannot collide
+        let mut placeholder_keys = HashMap::new();
+        let mut chunks = HashMap::new();
+
+        for (chunk, tiles) in tiles {
+            let mut map_raw = MapRaw {
+                map_name: self.map_name.clone(),
+                ..Default::default()
+            };
+            let mut entities = vec![];
+
+            for (coord, id) in tiles {
+                if let Some(tile_entity) = tile_entities.get(&coord) {
+                    entities.push((coord, id, tile_entity));
+                }
+            }
+
+            let data = multi_call_iter(
+                entities.iter().map(|(.., tile_entity)| *tile_entity),
+                entities.len(),
+                TileEntityMsg::GetData,
+                None,
+            )
+            .await
+            .map_err(|e| anyhow!("could not ask the tiles for their data: {e:?}"))?;
+
+            for ((coord, id, _), data) in entities.into_iter().zip(data) {
+                let CallResult::Success(data) = data else {
+                    bail!("the tile at {coord:?} did not send its data");
+                };
+
+                if let Some((raw_id, data)) = self.placeholder_of(resource_man, id, &data) {
+                    let next = Id::from((interner.len() + placeholder_keys.len()) as INT);
+                    let key = *placeholder_keys.entry(raw_id.clone()).or_insert(next);
+
+                    map_raw.tile_map.insert(key, raw_id.clone());
+                    map_raw.tiles.push((coord, key, data.clone()));
+
+                    continue;
                 }
 
-                let data = tile_entity
-                    .call(TileEntityMsg::GetData, None)
-                    .await
-                    .unwrap()
-                    .unwrap();
-                let data = data.to_raw(interner);
+                let Some(name) = interner.resolve(id) else {
+                    bail!("the tile at {coord:?} has an ID that does not exist");
+                };
 
-                map_raw.tiles.push((*coord, *id, data));
+                map_raw
+                    .tile_map
+                    .entry(id)
+                    .or_insert_with(|| IdRaw::parse(name));
+                map_raw.tiles.push((coord, id, data.to_raw(interner)));
             }
+
+            chunks.insert(chunk, map_raw);
         }
 
-        ron::ser::to_writer(
-            &mut info_writer,
-            &MapInfoRaw {
-                data: self.info.lock().await.data.to_raw(interner),
-                tile_count: self.tiles.len() as u64,
-            },
-        )
-        .unwrap();
+        let info = self.info.lock().await;
+        let info = MapInfoRaw {
+            version: MAP_VERSION,
+            data: info.data.to_raw(interner),
+            tile_count: self.tiles.len() as u64,
+            codec: self.codec,
+            description: info.description.clone(),
+            play_time: info.play_time.as_secs(),
+            tick_count: info.tick_count,
+            summary: MapSummary::default(),
+        };
+
+        Ok(MapSnapshot {
+            map_name: self.map_name.clone(),
+            chunks,
+            kept,
+            info,
+        })
+    }
+
+    /// Rewrites a map's tiles with another codec, upgrading the map to the current version on the way.
+    ///
+    /// The converted chunks are written next to the old ones and swapped in at the end,
+    /// so that a failed conversion leaves the map as it was.
+    pub fn convert(map_name: &str, codec: MapCodec) -> anyhow::Result<()> {
+        let saved = Map::read_header(map_name)?;
+        let map = Map::read_all_tiles(map_name, &saved)?;
+
+        let mut info = saved;
+        migrate(&mut info, &mut MapRaw::default())?;
+        info.codec = codec;
+
+        Map::replace_tiles(map_name, info, map)?;
+
+        log::info!("Converted map {map_name} to {codec:?}");
+
+        Ok(())
+    }
+
+    /// Shrinks a picture of the map down to a thumbnail and saves it next to the map.
+    pub fn save_thumbnail(map_name: &str, image: &RgbaImage) -> anyhow::Result<()> {
+        let height = (image.height() * THUMBNAIL_WIDTH / image.width().max(1)).max(1);
+        let thumbnail =
+            image::imageops::resize(image, THUMBNAIL_WIDTH, height, FilterType::Triangle);
+
+        Map::write_atomically(&Map::thumbnail(map_name), |mut writer| {
+            thumbnail.write_to(&mut writer, ImageOutputFormat::Png)?;
+
+            Ok(writer)
+        })
+    }
+
+    /// Changes the description in a map's header, leaving everything else as it was.
+    pub fn set_description(map_name: &str, description: String) -> anyhow::Result<()> {
+        let mut info = Map::read_header(map_name)?;
+        info.description = description;
+
+        Map::write_atomically(&Map::info(map_name), |mut writer| {
+            ron::ser::to_writer(&mut writer, &info)?;
+
+            Ok(writer)
+        })
+    }
+
+    /// Reads the header of a map as it is saved, without migrating it.
+    pub fn read_header(map_name: &str) -> anyhow::Result<MapInfoRaw> {
+        let info = BufReader::new(File::open(Map::info(map_name))?);
+
+        Ok(ron::de::from_reader(info)?)
+    }
+
+    /// Reads every tile of a map into a single MapRaw, migrated to the current version.
+    ///
+    /// `saved` is the header as it is saved, which decides where the tiles are and how they are encoded.
+    pub fn read_all_tiles(map_name: &str, saved: &MapInfoRaw) -> anyhow::Result<MapRaw> {
+        let mut chunks = Vec::new();
+
+        for path in Map::tile_files(map_name, saved.version) {
+            let mut chunk = MapRaw::decode(File::open(&path)?, saved.codec)?;
+            migrate(&mut saved.clone(), &mut chunk)?;
+
+            chunks.push(chunk);
+        }
+
+        Ok(MapRaw::join(map_name.to_string(), chunks))
+    }
+
+    /// Replaces all the tiles of a map, writing them in chunks with the codec in the header.
+    ///
+    /// The new chunks are swapped in before the header is written, so the map is never left half written.
+    pub fn replace_tiles(map_name: &str, mut info: MapInfoRaw, map: MapRaw) -> anyhow::Result<()> {
+        info.tile_count = map.tiles.len() as u64;
+        info.summary = MapSummary::default();
+        info.summary.add(&map);
+
+        let chunks = Map::chunks(map_name);
+        let replacement = Map::temp_path(&chunks);
+
+        drop(fs::remove_dir_all(&replacement));
+        fs::create_dir_all(&replacement)?;
+
+        for (coord, chunk) in map.into_chunks() {
+            let path = replacement.join(Map::chunk_file(coord));
+
+            Map::write_atomically(&path, |writer| chunk.encode(writer, info.codec))?;
+        }
 
-        ron::ser::to_writer(&mut tiles_encoder, &map_raw).unwrap();
+        Map::swap_in_chunks(&Map::path(map_name), &replacement, || {
+            Map::write_atomically(&Map::info(map_name), |mut writer| {
+                ron::ser::to_writer(&mut writer, &info)?;
 
-        info_writer.flush().unwrap();
-        tiles_encoder.do_finish().unwrap();
+                Ok(writer)
+            })
+        })
     }
 
     /// Sanitizes the name to ensure that the map can be used without problems on all platforms. This includes removing leading/trailing whitespace and periods, replacing non-alphanumeric characters, and replacing Windows disallowed names.
@@ -289,6 +1380,8 @@ impl Map {
 }
 
 lazy_static! {
+    /// The folder the maps are in, which is `MAP_PATH` unless it is set to another one.
+    static ref MAP_ROOT: RwLock<PathBuf> = RwLock::new(PathBuf::from(MAP_PATH));
     static ref WIN_ILLEGAL_NAMES: HashSet<&'static str> = HashSet::from([
         "CON", "PRN", "AUX", "CLOCK$", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6",
         "COM7", "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8",
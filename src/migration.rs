@@ -0,0 +1,65 @@
+use std::mem;
+
+use automancy_resources::data::DataRaw;
+
+use crate::map::{MapInfoRaw, MapRaw};
+
+/// Upgrades a map from one version of the format to the next.
+pub type Migration = fn(&mut MapInfoRaw, &mut MapRaw) -> anyhow::Result<()>;
+
+/// The migrations of the map data, where the one at index N upgrades a map from version N to version N + 1.
+/// Maps saved before the format was versioned are version 0.
+///
+/// The versions past the last migration only changed how maps are laid out on disk,
+/// which is handled where maps are read, as noted on the version constants below.
+pub const MIGRATIONS: &[Migration] = &[unlocked_researches_to_set];
+
+/// The first version that stores the tiles in chunks instead of in a single file.
+/// Older maps are read from their single file by `Map::tile_files`, and written out as chunks by the next save.
+pub const CHUNKED_VERSION: u32 = 2;
+
+/// The first version that records how the tiles are encoded in the header.
+/// Older headers have no codec, which defaults to RON when the header is read.
+pub const CODEC_VERSION: u32 = 3;
+
+/// The version of the format maps are saved in.
+pub const MAP_VERSION: u32 = CODEC_VERSION;
+
+/// Upgrades the map to the current version, one version at a time.
+/// Fails if the map was saved by a newer version of the game, or if a migration fails.
+pub fn migrate(info: &mut MapInfoRaw, map: &mut MapRaw) -> anyhow::Result<()> {
+    if info.version > MAP_VERSION {
+        anyhow::bail!(
+            "the map is version {}, but the newest supported version is {MAP_VERSION}",
+            info.version
+        );
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(info.version as usize) {
+        migration(info, map)
+            .map_err(|e| e.context(format!("could not migrate the map from version {version}")))?;
+
+        info.version = version as u32 + 1;
+    }
+
+    info.version = MAP_VERSION;
+
+    Ok(())
+}
+
+/// Version 0 stored the unlocked researches as a list. They are a set since version 1.
+fn unlocked_researches_to_set(info: &mut MapInfoRaw, _map: &mut MapRaw) -> anyhow::Result<()> {
+    let mut data = mem::take(&mut info.data).into_inner();
+
+    for (key, value) in data.iter_mut() {
+        if key == "automancy:unlocked_researches" || key == "unlocked_researches" {
+            if let DataRaw::VecId(ids) = value {
+                *value = DataRaw::SetId(mem::take(ids));
+            }
+        }
+    }
+
+    info.data = data.into();
+
+    Ok(())
+}
@@ -17,6 +17,8 @@ pub struct Options {
     pub graphics: GraphicsOptions,
     pub audio: AudioOptions,
     pub gui: GuiOptions,
+    #[serde(default)]
+    pub game: GameOptions,
     pub keymap: HashMap<Key, KeyAction>,
     pub synced: bool,
 }
@@ -27,6 +29,7 @@ impl Default for Options {
             graphics: Default::default(),
             audio: Default::default(),
             gui: Default::default(),
+            game: Default::default(),
             keymap: DEFAULT_KEYMAP.iter().cloned().collect(),
             synced: false,
         }
@@ -119,6 +122,24 @@ impl Default for GuiOptions {
         }
     }
 }
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameOptions {
+    /// the most changes that can be undone, or 0 for no limit
+    pub undo_depth: usize,
+    /// how many minutes there are between autosaves, or 0 to never autosave
+    pub autosave_interval: u64,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            undo_depth: 0,
+            autosave_interval: 5,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct AudioOptions {
     pub sfx_volume: f64,
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::mem;
+
+use hashbrown::HashMap;
+
+use automancy_defs::id::IdRaw;
+use automancy_resources::data::{DataMapRaw, DataRaw};
+use automancy_resources::ResourceManager;
+
+use crate::map::{Map, MapInfoRaw, MapRaw};
+use crate::migration::migrate;
+
+/// What an ID that is missing from the game was used as in a map, which decides what it can be replaced with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MissingIdKind {
+    Tile,
+    Item,
+    Script,
+    Data,
+}
+
+/// The IDs of a map that are missing from the game, along with what they were used as.
+pub type MissingIds = BTreeMap<IdRaw, MissingIdKind>;
+
+/// Replacements for the IDs of a map that are missing from the game.
+/// Missing tiles without a replacement are kept as placeholders, everything else is left out.
+pub type IdRemap = HashMap<IdRaw, IdRaw>;
+
+fn exists(resource_man: &ResourceManager, id: &IdRaw, kind: MissingIdKind) -> bool {
+    let Some(id) = resource_man.interner.get(id.to_string()) else {
+        return false;
+    };
+
+    match kind {
+        MissingIdKind::Tile => resource_man.registry.tiles.contains_key(&id),
+        MissingIdKind::Item => resource_man.registry.items.contains_key(&id),
+        MissingIdKind::Script => resource_man.registry.scripts.contains_key(&id),
+        MissingIdKind::Data => true,
+    }
+}
+
+fn check(
+    resource_man: &ResourceManager,
+    missing: &mut MissingIds,
+    id: &IdRaw,
+    kind: MissingIdKind,
+) {
+    if !exists(resource_man, id, kind) {
+        // an ID used as several things keeps the most specific one
+        missing
+            .entry(id.clone())
+            .and_modify(|v| *v = (*v).min(kind))
+            .or_insert(kind);
+    }
+}
+
+fn collect_data(resource_man: &ResourceManager, missing: &mut MissingIds, data: &DataMapRaw) {
+    let data_ids = &resource_man.registry.data_ids;
+
+    for (key, value) in data.iter() {
+        let key = IdRaw::parse(key);
+        let key_id = resource_man.interner.get(key.to_string());
+
+        check(resource_man, missing, &key, MissingIdKind::Data);
+
+        let kind = if key_id == Some(data_ids.script) {
+            MissingIdKind::Script
+        } else if key_id == Some(data_ids.item) {
+            MissingIdKind::Item
+        } else {
+            MissingIdKind::Data
+        };
+
+        match value {
+            DataRaw::Inventory(inventory) => {
+                for (id, _) in inventory.iter() {
+                    check(resource_man, missing, id, MissingIdKind::Item);
+                }
+            }
+            DataRaw::Id(id) => check(resource_man, missing, id, kind),
+            DataRaw::VecId(ids) | DataRaw::SetId(ids) => {
+                for id in ids {
+                    check(resource_man, missing, id, kind);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_tiles(resource_man: &ResourceManager, missing: &mut MissingIds, map: &MapRaw) {
+    for id in map.tile_map.values() {
+        check(resource_man, missing, id, MissingIdKind::Tile);
+    }
+
+    for (_, _, data) in &map.tiles {
+        collect_data(resource_man, missing, data);
+    }
+}
+
+/// Collects every tile, item, script and data ID of a map that does not exist in the game.
+pub fn collect_missing_ids(
+    resource_man: &ResourceManager,
+    info: &MapInfoRaw,
+    map: &MapRaw,
+) -> MissingIds {
+    let mut missing = MissingIds::new();
+
+    collect_data(resource_man, &mut missing, &info.data);
+    collect_tiles(resource_man, &mut missing, map);
+
+    missing
+}
+
+fn remap_id(id: &mut IdRaw, remap: &IdRemap) {
+    if let Some(new) = remap.get(id) {
+        *id = new.clone();
+    }
+}
+
+fn remap_data(data: &mut DataMapRaw, remap: &IdRemap) {
+    for (key, mut value) in mem::take(&mut **data) {
+        match &mut value {
+            DataRaw::Inventory(inventory) => {
+                for (id, _) in inventory.iter_mut() {
+                    remap_id(id, remap);
+                }
+            }
+            DataRaw::Id(id) => remap_id(id, remap),
+            DataRaw::VecId(ids) | DataRaw::SetId(ids) => {
+                for id in ids.iter_mut() {
+                    remap_id(id, remap);
+                }
+            }
+            _ => {}
+        }
+
+        let key = match remap.get(&IdRaw::parse(&key)) {
+            Some(new) => new.to_string(),
+            None => key,
+        };
+
+        data.insert(key, value);
+    }
+}
+
+/// Replaces the IDs of a map with the ones they are remapped to.
+pub fn remap_ids(info: &mut MapInfoRaw, map: &mut MapRaw, remap: &IdRemap) {
+    if remap.is_empty() {
+        return;
+    }
+
+    for id in map.tile_map.values_mut() {
+        remap_id(id, remap);
+    }
+
+    remap_data(&mut info.data, remap);
+
+    for (_, _, data) in map.tiles.iter_mut() {
+        remap_data(data, remap);
+    }
+}
+
+impl Map {
+    /// Reads a map from disk and collects the IDs it uses that do not exist in the game.
+    ///
+    /// Returns None if the map cannot be read, in which case loading it reports the actual error.
+    pub fn missing_ids(resource_man: &ResourceManager, map_name: &str) -> Option<MissingIds> {
+        let saved = Map::read_header(map_name).ok()?;
+
+        let mut info = saved.clone();
+        migrate(&mut info, &mut MapRaw::default()).ok()?;
+
+        let mut missing = collect_missing_ids(resource_man, &info, &MapRaw::default());
+
+        for path in Map::tile_files(map_name, saved.version) {
+            let mut map = MapRaw::decode(File::open(path).ok()?, saved.codec).ok()?;
+            migrate(&mut saved.clone(), &mut map).ok()?;
+
+            collect_tiles(resource_man, &mut missing, &map);
+        }
+
+        Some(missing)
+    }
+}
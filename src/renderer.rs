@@ -1,13 +1,14 @@
 use std::borrow::Cow;
 use std::f32::consts::FRAC_PI_6;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
 use arboard::{Clipboard, ImageData};
 use egui::Rgba;
 use egui_wgpu::wgpu::{
-    BufferAddress, BufferDescriptor, BufferUsages, Color, CommandEncoderDescriptor,
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferAddress,
+    BufferDescriptor, BufferUsages, Color, CommandEncoder, CommandEncoderDescriptor, Extent3d,
     ImageCopyBuffer, ImageDataLayout, IndexFormat, LoadOp, Maintain, MapMode, Operations,
     RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
     SurfaceError, TextureDescriptor, TextureDimension, TextureUsages, TextureViewDescriptor,
@@ -25,6 +26,7 @@ use automancy_defs::coord::TileCoord;
 use automancy_defs::glam::vec3;
 use automancy_defs::gui::Gui;
 use automancy_defs::id::Id;
+use automancy_defs::log;
 use automancy_defs::math::{
     direction_to_angle, lerp_coords_to_pixel, Double, Float, Matrix4, FAR, HEX_GRID_LAYOUT, SQRT_3,
 };
@@ -44,6 +46,7 @@ use crate::gpu::{
     SCREENSHOT_FORMAT,
 };
 use crate::input::KeyActions;
+use crate::map::Map;
 use crate::options::Options;
 use crate::setup::GameSetup;
 
@@ -53,11 +56,15 @@ pub struct Renderer<'a> {
     pub render_resources: RenderResources,
     pub global_buffers: Arc<GlobalBuffers>,
     pub fps_limit: Double,
+    /// The name of the map to take a thumbnail of on the next frame, if any.
+    pub thumbnail_request: Option<String>,
 
     render_info_cache: Arc<Mutex<Option<RenderInfo>>>,
     render_info_updating: Arc<AtomicBool>,
     transaction_records_cache: Arc<Mutex<TransactionRecords>>,
     transaction_records_updating: Arc<AtomicBool>,
+    /// bumped when the caches are forgotten, so that the updates started before then drop what they bring back
+    cache_generation: Arc<AtomicU64>,
 }
 
 impl<'a> Renderer<'a> {
@@ -74,13 +81,141 @@ impl<'a> Renderer<'a> {
             render_resources,
             global_buffers,
             fps_limit: options.graphics.fps_limit,
+            thumbnail_request: None,
 
             render_info_cache: Arc::new(Default::default()),
             render_info_updating: Arc::new(Default::default()),
             transaction_records_cache: Arc::new(Default::default()),
             transaction_records_updating: Arc::new(Default::default()),
+            cache_generation: Arc::new(Default::default()),
         }
     }
+
+    /// Forgets what was taken from the game, as the IDs in it do not match the ones of reloaded resources.
+    /// The updates still running are not waited for, and what they bring back is dropped.
+    pub fn forget_game_info(&self) {
+        self.cache_generation.fetch_add(1, Ordering::SeqCst);
+
+        *self.render_info_cache.blocking_lock() = None;
+        self.transaction_records_cache.blocking_lock().clear();
+    }
+}
+
+fn size_align<T: PrimInt>(size: T, alignment: T) -> T {
+    ((size + alignment - T::one()) / alignment) * alignment
+}
+
+impl Renderer<'_> {
+    /// Draws the texture of a bind group in the screenshot format, and copies it into a buffer that can be read back.
+    fn copy_to_buffer(
+        &self,
+        encoder: &mut CommandEncoder,
+        bind_group: &BindGroup,
+        texture_dim: Extent3d,
+    ) -> Buffer {
+        let block_size = SCREENSHOT_FORMAT.block_copy_size(None).unwrap();
+        let buffer_dim = texture_dim.physical_size(SCREENSHOT_FORMAT);
+        let padded_width = size_align(buffer_dim.width * block_size, COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let intermediate_texture = self.gpu.device.create_texture(&TextureDescriptor {
+            label: Some("Screenshot Intermediate Texture"),
+            size: texture_dim,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: SCREENSHOT_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let intermediate_texture_view =
+            intermediate_texture.create_view(&TextureViewDescriptor::default());
+
+        {
+            let mut intermediate_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Screenshot Intermediate Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &intermediate_texture_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            intermediate_pass.set_pipeline(
+                &self
+                    .render_resources
+                    .intermediate_resources
+                    .screenshot_pipeline,
+            );
+            intermediate_pass.set_bind_group(0, bind_group, &[]);
+            intermediate_pass.draw(0..3, 0..1);
+        }
+
+        let buffer = self.gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("Screenshot Buffer"),
+            size: size_align(
+                (padded_width * buffer_dim.height) as BufferAddress,
+                COPY_BUFFER_ALIGNMENT,
+            ),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            intermediate_texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_width),
+                    rows_per_image: Some(buffer_dim.height),
+                },
+            },
+            buffer_dim,
+        );
+
+        buffer
+    }
+
+    /// Reads a buffer filled by `copy_to_buffer` back into an image. The commands filling it must be submitted first.
+    fn read_buffer(&self, buffer: Buffer, texture_dim: Extent3d) -> Option<RgbaImage> {
+        let block_size = SCREENSHOT_FORMAT.block_copy_size(None).unwrap();
+        let buffer_dim = texture_dim.physical_size(SCREENSHOT_FORMAT);
+        let padded_width = size_align(buffer_dim.width * block_size, COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let image = {
+            let slice = buffer.slice(..);
+
+            let (tx, rx) = oneshot::channel();
+
+            slice.map_async(MapMode::Read, move |result| {
+                tx.send(result).unwrap();
+            });
+            self.gpu.device.poll(Maintain::Wait);
+            rx.blocking_recv().unwrap().unwrap();
+
+            let texture_width = (texture_dim.width * block_size) as usize;
+            let data = slice.get_mapped_range();
+            let mut result = Vec::<u8>::new();
+            for chunk in data.chunks_exact(padded_width as usize) {
+                for pixel in chunk[..texture_width].chunks_exact(4) {
+                    result.extend(&[pixel[0], pixel[1], pixel[2], 255]);
+                }
+            }
+
+            RgbaImage::from_vec(texture_dim.width, texture_dim.height, result)
+        };
+
+        buffer.unmap();
+
+        image
+    }
 }
 
 fn get_angle_from_direction(target: &Data) -> Option<Float> {
@@ -149,6 +284,8 @@ impl<'a> Renderer<'a> {
         if !self.render_info_updating.load(Ordering::Relaxed) {
             let cache = self.render_info_cache.clone();
             let updating = self.render_info_updating.clone();
+            let generation = self.cache_generation.clone();
+            let started_in = generation.load(Ordering::SeqCst);
             let game = setup.game.clone();
 
             updating.store(true, Ordering::Relaxed);
@@ -166,7 +303,11 @@ impl<'a> Renderer<'a> {
                     .unwrap()
                     .unwrap();
 
-                *cache.lock().await = Some(result);
+                let mut cache = cache.lock().await;
+                if generation.load(Ordering::SeqCst) == started_in {
+                    *cache = Some(result);
+                }
+                drop(cache);
 
                 updating.store(false, Ordering::Relaxed);
             });
@@ -179,6 +320,8 @@ impl<'a> Renderer<'a> {
         if !self.transaction_records_updating.load(Ordering::Relaxed) {
             let cache = self.transaction_records_cache.clone();
             let updating = self.transaction_records_updating.clone();
+            let generation = self.cache_generation.clone();
+            let started_in = generation.load(Ordering::SeqCst);
             let game = setup.game.clone();
 
             updating.store(true, Ordering::Relaxed);
@@ -190,7 +333,11 @@ impl<'a> Renderer<'a> {
                     .unwrap()
                     .unwrap();
 
-                *cache.lock().await = result;
+                let mut cache = cache.lock().await;
+                if generation.load(Ordering::SeqCst) == started_in {
+                    *cache = result;
+                }
+                drop(cache);
 
                 updating.store(false, Ordering::Relaxed);
             });
@@ -841,131 +988,80 @@ impl<'a> Renderer<'a> {
             present_pass.draw(0..3, 0..1)
         }
 
-        fn size_align<T: PrimInt>(size: T, alignment: T) -> T {
-            ((size + alignment - T::one()) / alignment) * alignment
-        }
-
-        let block_size = output.texture.format().block_copy_size(None).unwrap();
         let texture_dim = output.texture.size();
-        let buffer_dim = texture_dim.physical_size(output.texture.format());
-        let padded_width = size_align(buffer_dim.width * block_size, COPY_BYTES_PER_ROW_ALIGNMENT);
 
         let screenshot_buffer = if setup.input_handler.key_active(KeyActions::Screenshot) {
-            let intermediate_texture = self.gpu.device.create_texture(&TextureDescriptor {
-                label: Some("Screenshot Intermediate Texture"),
-                size: texture_dim,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: SCREENSHOT_FORMAT,
-                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
-                view_formats: &[],
-            });
-
-            let intermediate_texture_view =
-                intermediate_texture.create_view(&TextureViewDescriptor::default());
-
-            {
-                let mut intermediate_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                    label: Some("Screenshot Intermediate Pass"),
-                    color_attachments: &[Some(RenderPassColorAttachment {
-                        view: &intermediate_texture_view,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Load,
-                            store: StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    occlusion_query_set: None,
-                    timestamp_writes: None,
-                });
-
-                intermediate_pass.set_pipeline(
-                    &self
-                        .render_resources
-                        .intermediate_resources
-                        .screenshot_pipeline,
-                );
-                intermediate_pass.set_bind_group(
-                    0,
+            Some(
+                self.copy_to_buffer(
+                    &mut encoder,
                     self.render_resources
                         .intermediate_resources
                         .present_bind_group(),
-                    &[],
-                );
-                intermediate_pass.draw(0..3, 0..1);
-            }
-
-            let buffer = self.gpu.device.create_buffer(&BufferDescriptor {
-                label: Some("Screenshot Buffer"),
-                size: size_align(
-                    (padded_width * buffer_dim.height) as BufferAddress,
-                    COPY_BUFFER_ALIGNMENT,
+                    texture_dim,
                 ),
-                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
-
-            encoder.copy_texture_to_buffer(
-                intermediate_texture.as_image_copy(),
-                ImageCopyBuffer {
-                    buffer: &buffer,
-                    layout: ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(padded_width),
-                        rows_per_image: Some(buffer_dim.height),
-                    },
-                },
-                buffer_dim,
-            );
-
-            Some(buffer)
+            )
         } else {
             None
         };
 
+        // thumbnails only show the game, without the GUI on top
+        let thumbnail_buffer = self.thumbnail_request.take().map(|map_name| {
+            let bind_group = self.gpu.device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Thumbnail Bind Group"),
+                layout: &self
+                    .render_resources
+                    .intermediate_resources
+                    .bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(
+                            &self
+                                .render_resources
+                                .game_resources
+                                .antialiasing_texture()
+                                .1,
+                        ),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(
+                            &self.shared_resources.non_filtering_sampler,
+                        ),
+                    },
+                ],
+            });
+
+            (
+                map_name,
+                self.copy_to_buffer(&mut encoder, &bind_group, texture_dim),
+            )
+        });
+
         self.gpu
             .queue
             .submit(user_commands.into_iter().chain([encoder.finish()]));
 
-        if let Some(buffer) = screenshot_buffer {
-            {
-                let slice = buffer.slice(..);
-
-                let (tx, rx) = oneshot::channel();
-
-                slice.map_async(MapMode::Read, move |result| {
-                    tx.send(result).unwrap();
-                });
-                self.gpu.device.poll(Maintain::Wait);
-                rx.blocking_recv().unwrap().unwrap();
-
-                let texture_width = (texture_dim.width * block_size) as usize;
-                let data = slice.get_mapped_range();
-                let mut result = Vec::<u8>::new();
-                for chunk in data.chunks_exact(padded_width as usize) {
-                    for pixel in chunk[..texture_width].chunks_exact(4) {
-                        result.extend(&[pixel[0], pixel[1], pixel[2], 255]);
-                    }
-                }
+        if let Some(image) =
+            screenshot_buffer.and_then(|buffer| self.read_buffer(buffer, texture_dim))
+        {
+            let mut clipboard = Clipboard::new().unwrap();
+
+            clipboard
+                .set_image(ImageData {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    bytes: Cow::from(image.as_bytes()),
+                })
+                .unwrap();
+        }
 
-                if let Some(image) =
-                    RgbaImage::from_vec(texture_dim.width, texture_dim.height, result)
-                {
-                    let mut clipboard = Clipboard::new().unwrap();
-
-                    clipboard
-                        .set_image(ImageData {
-                            width: image.width() as usize,
-                            height: image.height() as usize,
-                            bytes: Cow::from(image.as_bytes()),
-                        })
-                        .unwrap();
+        if let Some((map_name, buffer)) = thumbnail_buffer {
+            if let Some(image) = self.read_buffer(buffer, texture_dim) {
+                if let Err(e) = Map::save_thumbnail(&map_name, &image) {
+                    log::error!("Could not save the thumbnail of map {map_name}: {e:?}");
                 }
             }
-
-            buffer.unmap();
         }
 
         output.present();
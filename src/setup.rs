@@ -1,7 +1,9 @@
 use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::{Instant, SystemTime};
 
+use anyhow::Context;
 use ractor::concurrency::JoinHandle;
 use ractor::{Actor, ActorRef};
 
@@ -14,59 +16,94 @@ use automancy_resources::{ResourceManager, RESOURCES_PATH, RESOURCE_MAN};
 use crate::camera::Camera;
 use crate::game::{Game, GameMsg, TICK_INTERVAL};
 use crate::input::InputHandler;
-use crate::map::{Map, MapInfoRaw, MAP_PATH};
+use crate::map::{Map, MapInfoRaw};
 use crate::options::Options;
 
+/// Loads all the resources of a namespace.
+fn load_namespace(resource_man: &mut ResourceManager, dir: &Path) -> anyhow::Result<()> {
+    resource_man
+        .load_models(dir)
+        .context("Error loading models")?;
+    resource_man
+        .load_audio(dir)
+        .context("Error loading audio")?;
+    resource_man
+        .load_tiles(dir)
+        .context("Error loading tiles")?;
+    resource_man
+        .load_items(dir)
+        .context("Error loading items")?;
+    resource_man.load_tags(dir).context("Error loading tags")?;
+    resource_man
+        .load_categories(dir)
+        .context("Error loading categories")?;
+    resource_man
+        .load_scripts(dir)
+        .context("Error loading scripts")?;
+    resource_man
+        .load_translates(dir)
+        .context("Error loading translates")?;
+    resource_man
+        .load_shaders(dir)
+        .context("Error loading shaders")?;
+    resource_man
+        .load_fonts(dir)
+        .context("Error loading fonts")?;
+    resource_man
+        .load_functions(dir)
+        .context("Error loading functions")?;
+    resource_man
+        .load_researches(dir)
+        .context("Error loading researches")?;
+
+    Ok(())
+}
+
 /// Initialize the Resource Manager system, and loads all the resources in all namespaces.
-fn load_resources(track: TrackHandle) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
+/// Fails if any of them cannot be loaded.
+pub fn try_load_resources(
+    track: Arc<TrackHandle>,
+) -> anyhow::Result<(Arc<ResourceManager>, Vec<Vertex>, Vec<u16>)> {
     let mut resource_man = ResourceManager::new(track);
 
-    fs::read_dir(RESOURCES_PATH)
-        .expect("The resources folder doesn't exist- this is very wrong")
+    for dir in fs::read_dir(RESOURCES_PATH)
+        .context("The resources folder doesn't exist- this is very wrong")?
         .flatten()
         .map(|v| v.path())
-        .for_each(|dir| {
-            let namespace = dir.file_name().unwrap().to_str().unwrap();
-            log::info!("Loading namespace {namespace}...");
-
-            resource_man
-                .load_models(&dir)
-                .expect("Error loading models");
-            resource_man.load_audio(&dir).expect("Error loading audio");
-            resource_man.load_tiles(&dir).expect("Error loading tiles");
-            resource_man.load_items(&dir).expect("Error loading items");
-            resource_man.load_tags(&dir).expect("Error loading tags");
-            resource_man
-                .load_categories(&dir)
-                .expect("Error loading categories");
-            resource_man
-                .load_scripts(&dir)
-                .expect("Error loading scripts");
-            resource_man
-                .load_translates(&dir)
-                .expect("Error loading translates");
-            resource_man
-                .load_shaders(&dir)
-                .expect("Error loading shaders");
-            resource_man.load_fonts(&dir).expect("Error loading fonts");
-            resource_man
-                .load_functions(&dir)
-                .expect("Error loading functions");
-            resource_man
-                .load_researches(&dir)
-                .expect("Error loading researches");
-
-            log::info!("Loaded namespace {namespace}.");
-        });
+    {
+        let namespace = dir.file_name().unwrap().to_str().unwrap().to_string();
+        log::info!("Loading namespace {namespace}...");
 
-    resource_man.compile_researches();
+        load_namespace(&mut resource_man, &dir)
+            .with_context(|| format!("Error loading namespace {namespace}"))?;
+
+        log::info!("Loaded namespace {namespace}.");
+    }
+
+    resource_man
+        .compile_researches()
+        .context("Error compiling researches")?;
     resource_man.ordered_tiles();
     resource_man.ordered_items();
     resource_man.ordered_categories();
 
     let (vertices, indices) = resource_man.compile_models();
 
-    (Arc::new(resource_man), vertices, indices)
+    Ok((Arc::new(resource_man), vertices, indices))
+}
+
+/// Like try_load_resources, but panics if any of the resources cannot be loaded.
+pub fn load_resources(track: Arc<TrackHandle>) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
+    try_load_resources(track).expect("Error loading resources")
+}
+
+/// Spawns the game actor. Ticking is left to the caller.
+pub async fn spawn_game(
+    resource_man: Arc<ResourceManager>,
+) -> anyhow::Result<(ActorRef<GameMsg>, JoinHandle<()>)> {
+    let (game, game_handle) = Actor::spawn(Some("game".to_string()), Game, resource_man).await?;
+
+    Ok((game, game_handle))
 }
 
 /// Stores what the game initializes on startup.
@@ -97,11 +134,11 @@ impl GameSetup {
         // --- resources & data ---
         log::info!("Initializing audio backend...");
         let mut audio_man = AudioManager::new(AudioManagerSettings::default())?;
-        let track = audio_man.add_sub_track({
+        let track = Arc::new(audio_man.add_sub_track({
             let builder = TrackBuilder::new();
 
             builder
-        })?;
+        })?);
         log::info!("Audio backend initialized");
 
         log::info!("Loading resources...");
@@ -112,14 +149,7 @@ impl GameSetup {
 
         // --- game ---
         log::info!("Creating game...");
-        let (game, game_handle) = Actor::spawn(
-            Some("game".to_string()),
-            Game {
-                resource_man: resource_man.clone(),
-            },
-            (),
-        )
-        .await?;
+        let (game, game_handle) = spawn_game(resource_man.clone()).await?;
 
         game.send_interval(TICK_INTERVAL, || GameMsg::Tick);
 
@@ -149,11 +179,34 @@ impl GameSetup {
         ))
     }
 
+    /// Loads all the resources again, and swaps them in for the game and its tiles, so that changes to them show up without restarting.
+    /// Returns the vertices and indices of the reloaded models.
+    ///
+    /// If the resources cannot be loaded, the old ones are kept.
+    pub async fn reload_resources(&mut self) -> anyhow::Result<(Vec<Vertex>, Vec<u16>)> {
+        // the new resources play their sounds on the same track, so that reloading does not add tracks
+        let track = self.resource_man.track.clone();
+        let (resource_man, vertices, indices) = try_load_resources(track)?;
+
+        self.game
+            .call(
+                |reply| GameMsg::ReloadResources(resource_man.clone(), reply),
+                None,
+            )
+            .await?;
+
+        self.resource_man = resource_man;
+
+        log::info!("Reloaded resources.");
+
+        Ok((vertices, indices))
+    }
+
     /// Refreshes the list of maps on the filesystem. Should be done every time the list of maps could have changed (on map creation/delete and on game load).
     pub fn refresh_maps(&mut self) {
-        drop(fs::create_dir_all(MAP_PATH));
+        drop(fs::create_dir_all(Map::root()));
 
-        self.maps = fs::read_dir(MAP_PATH)
+        self.maps = fs::read_dir(Map::root())
             .expect("Map folder doesn't exist- is the disk full?")
             .flatten()
             .map(|f| f.file_name().to_str().unwrap().to_string())
@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::mem;
+
+use enum_map::{Enum, EnumMap};
+use hashbrown::HashMap;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::ItemStack;
+
+use crate::game::TPS;
+use crate::map::Tiles;
+
+/// The amount of seconds kept for the last minute, and the amount of minutes kept for the last hour.
+const SUB_WINDOWS: usize = 60;
+
+/// What happened to the items counted in a statistic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum)]
+pub enum StatisticKind {
+    /// The items were made by the tile from the outputs of its script, and taken in at the other end.
+    Produced,
+    /// The items were taken in by the tile.
+    Consumed,
+    /// The items were passed on through the tile, or sent out by it without being made there.
+    Moved,
+}
+
+/// The time span the statistics are summed up over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum)]
+pub enum StatisticWindow {
+    Second,
+    Minute,
+    Hour,
+}
+
+/// The amounts of an item, by what happened to them.
+pub type ItemCounts = EnumMap<StatisticKind, u64>;
+
+type Bucket = HashMap<(TileCoord, Id), ItemCounts>;
+
+fn merge(into: &mut Bucket, from: &Bucket) {
+    for (key, counts) in from {
+        let entry = into.entry(*key).or_default();
+
+        for (kind, amount) in counts {
+            entry[kind] += amount;
+        }
+    }
+}
+
+/// Pushes the bucket to the back of the deque, dropping the oldest one if the deque is over the limit.
+fn push_limited(deque: &mut VecDeque<Bucket>, bucket: Bucket, limit: usize) {
+    deque.push_back(bucket);
+
+    if deque.len() > limit {
+        deque.pop_front();
+    }
+}
+
+/// Aggregates the items going through every tile over the last second, minute, and hour.
+///
+/// The last second is kept per tick, the last minute per second, and the last hour per minute,
+/// so the longer windows also include the part of the current second or minute that has passed.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    /// the ticks of the last second
+    ticks: VecDeque<Bucket>,
+    /// the seconds of the last minute
+    seconds: VecDeque<Bucket>,
+    /// the minutes of the last hour
+    minutes: VecDeque<Bucket>,
+
+    /// what has been recorded in the current tick
+    current_tick: Bucket,
+    /// the completed ticks of the current second
+    current_second: Bucket,
+    /// the completed seconds of the current minute
+    current_minute: Bucket,
+
+    ticks_in_second: u64,
+    seconds_in_minute: usize,
+}
+
+/// The statistics summed up over a window.
+#[derive(Debug, Clone, Default)]
+pub struct StatisticsReport {
+    /// the tiles that are still on the map, with the counts of each item that went through them
+    pub tiles: HashMap<TileCoord, (Id, HashMap<Id, ItemCounts>)>,
+    /// the counts of each item, over all the tiles
+    pub items: HashMap<Id, ItemCounts>,
+}
+
+impl Statistics {
+    /// Records the stack in the current tick.
+    pub fn record(&mut self, coord: TileCoord, stack: ItemStack, kind: StatisticKind) {
+        if stack.amount <= 0 {
+            return;
+        }
+
+        self.current_tick.entry((coord, stack.item.id)).or_default()[kind] += stack.amount as u64;
+    }
+
+    /// Finishes the current tick, and moves every completed second and minute into the longer windows.
+    pub fn end_tick(&mut self) {
+        let tick = mem::take(&mut self.current_tick);
+        merge(&mut self.current_second, &tick);
+        push_limited(&mut self.ticks, tick, TPS as usize);
+
+        self.ticks_in_second += 1;
+        if self.ticks_in_second < TPS {
+            return;
+        }
+        self.ticks_in_second = 0;
+
+        let second = mem::take(&mut self.current_second);
+        merge(&mut self.current_minute, &second);
+        push_limited(&mut self.seconds, second, SUB_WINDOWS);
+
+        self.seconds_in_minute += 1;
+        if self.seconds_in_minute < SUB_WINDOWS {
+            return;
+        }
+        self.seconds_in_minute = 0;
+
+        let minute = mem::take(&mut self.current_minute);
+        push_limited(&mut self.minutes, minute, SUB_WINDOWS);
+    }
+
+    /// Sums up the statistics over the given window, leaving out the tiles that are no longer on the map.
+    pub fn report(&self, window: StatisticWindow, tiles: &Tiles) -> StatisticsReport {
+        let mut total = Bucket::new();
+
+        match window {
+            StatisticWindow::Second => {
+                self.ticks
+                    .iter()
+                    .for_each(|bucket| merge(&mut total, bucket));
+            }
+            StatisticWindow::Minute => {
+                self.seconds
+                    .iter()
+                    .for_each(|bucket| merge(&mut total, bucket));
+                merge(&mut total, &self.current_second);
+            }
+            StatisticWindow::Hour => {
+                self.minutes
+                    .iter()
+                    .for_each(|bucket| merge(&mut total, bucket));
+                merge(&mut total, &self.current_minute);
+                merge(&mut total, &self.current_second);
+            }
+        }
+
+        let mut report = StatisticsReport::default();
+
+        for ((coord, item), counts) in total {
+            let item_counts = report.items.entry(item).or_default();
+
+            for (kind, amount) in &counts {
+                item_counts[kind] += amount;
+            }
+
+            if let Some(id) = tiles.get(&coord) {
+                report
+                    .tiles
+                    .entry(coord)
+                    .or_insert_with(|| (*id, HashMap::new()))
+                    .1
+                    .insert(item, counts);
+            }
+        }
+
+        report
+    }
+}
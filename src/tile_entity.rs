@@ -1,26 +1,51 @@
 use std::mem;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
-use rand::{thread_rng, RngCore};
-use rhai::{CallFnOptions, Dynamic, ImmutableString, Scope, INT};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rhai::{CallFnOptions, Dynamic, Scope};
 
 use automancy_defs::coord::TileCoord;
 use automancy_defs::id::Id;
 use automancy_defs::log;
-use automancy_resources::data::stack::{ItemAmount, ItemStack};
+use automancy_resources::data::stack::ItemStack;
 use automancy_resources::data::{Data, DataMap};
-use automancy_resources::types::function::RhaiDataMap;
+use automancy_resources::types::function::{RhaiDataMap, TileAction, WorldSnapshot};
 use automancy_resources::ResourceManager;
 
 use crate::game::{GameMsg, TickUnit};
+use crate::map::MapSeed;
+use crate::statistics::StatisticKind;
 use crate::tile_entity::TileEntityMsg::*;
 
-fn rhai_call_options(rhai_state: &mut Dynamic) -> CallFnOptions {
+/// The random number generator given to tile entities.
+pub type TileRng = ChaCha8Rng;
+
+/// Creates the random number stream of the tile entity at the given coordinates, for the given tick.
+/// The same seed, coordinates and tick always result in the same stream.
+pub fn tile_rng(seed: MapSeed, coord: TileCoord, tick_count: TickUnit) -> TileRng {
+    let mut bytes = [0; 32];
+
+    bytes[0..4].copy_from_slice(&seed.to_le_bytes());
+    bytes[4..8].copy_from_slice(&coord.x.to_le_bytes());
+    bytes[8..12].copy_from_slice(&coord.y.to_le_bytes());
+    bytes[12..20].copy_from_slice(&tick_count.to_le_bytes());
+
+    TileRng::from_seed(bytes)
+}
+
+fn rhai_call_options<'a>(
+    rhai_state: &'a mut Dynamic,
+    world: &Weak<WorldSnapshot>,
+) -> CallFnOptions<'a> {
     CallFnOptions::new()
         .eval_ast(false)
         .rewind_scope(true)
         .bind_this_ptr(rhai_state)
+        // the game only lets go of the snapshot once every tile is done with the tick,
+        // so a message handled after that sees an empty world
+        .with_tag(world.upgrade().unwrap_or_default())
 }
 
 fn log_function_err(function_id: &str, err: &rhai::EvalAltResult) {
@@ -32,37 +57,81 @@ fn log_function_err(function_id: &str, err: &rhai::EvalAltResult) {
     }
 }
 
+/// Reads what a handler returned, which is either nothing, a TileAction, or an array of them to be done in order.
+/// Anything else is logged as an error of the function, and skipped.
+fn rhai_actions(function_id: &str, result: Dynamic) -> Vec<TileAction> {
+    if result.is_unit() {
+        return vec![];
+    }
+
+    let results = if result.is_array() {
+        result.cast::<rhai::Array>()
+    } else {
+        vec![result]
+    };
+
+    results
+        .into_iter()
+        .filter_map(|result| {
+            let ty = result.type_name();
+
+            let action = result.try_cast::<TileAction>();
+            if action.is_none() {
+                log::error!("In {function_id}: expected a TileAction to be returned, found {ty}");
+            }
+
+            action
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct TileEntity {
     /// The ID of the tile entity.
     pub id: Id,
     /// The coordinates of the tile entity.
     pub coord: TileCoord,
+    /// The random seed of the map the tile entity is in.
+    pub seed: MapSeed,
     /// The handle to the Resource Manager
     pub resource_man: Arc<ResourceManager>,
 }
 
 /// Represents a tile entity's state. A tile entity is the actor that allows the tile to take, process, and output resources.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct TileEntityState {
-    /// A handle to the game.
-    game: ActorRef<GameMsg>,
+    /// The messages to be delivered by the game, collected until the game takes them.
+    outbox: Vec<GameMsg>,
 
     /// The data map stored by the tile.
     data: RhaiDataMap,
+    /// Whether the data was replaced since the game last took the outbox.
+    data_replaced: bool,
 
     /// Rhai scope
     scope: Option<Scope<'static>>,
+
+    /// The random number stream of the current tick.
+    rng: TileRng,
+
+    /// The tiles of the map as they were at the start of the current tick.
+    /// Only the game keeps the snapshot alive, so that it can update it in place for the next tick.
+    world: Weak<WorldSnapshot>,
 }
 
 impl TileEntityState {
-    fn new(game: ActorRef<GameMsg>) -> Self {
+    fn new(rng: TileRng) -> Self {
         Self {
-            game,
+            outbox: Default::default(),
 
             data: Default::default(),
+            data_replaced: false,
 
             scope: Default::default(),
+
+            rng,
+
+            world: Default::default(),
         }
     }
 }
@@ -71,6 +140,7 @@ impl TileEntityState {
 pub enum TileEntityMsg {
     Tick {
         tick_count: TickUnit,
+        world: Arc<WorldSnapshot>,
     },
     Transaction {
         stack: ItemStack,
@@ -94,29 +164,44 @@ pub enum TileEntityMsg {
     GetData(RpcReplyPort<DataMap>),
     GetDataValue(Id, RpcReplyPort<Option<Data>>),
     GetDataWithCoord(RpcReplyPort<(TileCoord, DataMap)>),
+    /// Takes the messages the tile entity has produced since the last time this was sent.
+    TakeOutbox(RpcReplyPort<Vec<GameMsg>>),
 }
 
 impl TileEntity {
+    /// Whether the stack is one of the outputs of the tile's script, which the tile makes rather than passes along.
+    fn is_script_output(&self, state: &TileEntityState, stack: ItemStack) -> bool {
+        let Some(Data::Id(script)) = state.data.get(self.resource_man.registry.data_ids.script)
+        else {
+            return false;
+        };
+
+        self.resource_man
+            .registry
+            .scripts
+            .get(&script)
+            .is_some_and(|script| {
+                script
+                    .instructions
+                    .outputs
+                    .iter()
+                    .any(|output| output.item == stack.item)
+            })
+    }
+
     fn handle_rhai_transaction_result(
         &self,
         state: &mut TileEntityState,
+        function_id: &str,
         stack: ItemStack,
         source_coord: TileCoord,
         source_id: Id,
         root_coord: TileCoord,
         root_id: Id,
-        result: rhai::Array,
+        action: TileAction,
     ) -> Option<GameMsg> {
-        if result.is_empty() {
-            return None;
-        }
-
-        let ty: ImmutableString = result[0].clone().cast();
-
-        match ty.as_str() {
-            "pass_on" => {
-                let coord: TileCoord = result[1].clone().cast();
-
+        match action {
+            TileAction::PassOn(coord) => {
                 send_to_tile(
                     state,
                     coord,
@@ -129,12 +214,11 @@ impl TileEntity {
                         hidden: false,
                     },
                 );
+                record_statistic(state, self.coord, stack, StatisticKind::Moved);
 
-                return Some(GameMsg::RecordTransaction(stack, source_coord, self.coord));
+                Some(GameMsg::RecordTransaction(stack, source_coord, self.coord))
             }
-            "proxy" => {
-                let coord: TileCoord = result[1].clone().cast();
-
+            TileAction::Proxy(coord) => {
                 send_to_tile(
                     state,
                     coord,
@@ -147,74 +231,45 @@ impl TileEntity {
                         hidden: false,
                     },
                 );
+                record_statistic(state, self.coord, stack, StatisticKind::Moved);
 
-                return Some(GameMsg::RecordTransaction(stack, self.coord, coord));
+                Some(GameMsg::RecordTransaction(stack, self.coord, coord))
             }
-            "consume" => {
-                let consumed: ItemAmount = result[1].clone().cast();
+            TileAction::Consume(consumed) => {
+                let consumed = ItemStack {
+                    item: stack.item,
+                    amount: consumed,
+                };
 
-                send_to_tile(
-                    state,
-                    root_coord,
-                    TransactionResult {
-                        result: ItemStack {
-                            item: stack.item,
-                            amount: consumed,
-                        },
-                    },
+                send_to_tile(state, root_coord, TransactionResult { result: consumed });
+                record_statistic(state, self.coord, consumed, StatisticKind::Consumed);
+
+                Some(GameMsg::RecordTransaction(stack, source_coord, self.coord))
+            }
+            action => {
+                log::error!(
+                    "In {function_id}: {action:?} cannot be returned from handle_transaction"
                 );
 
-                return Some(GameMsg::RecordTransaction(stack, source_coord, self.coord));
+                None
             }
-            _ => (),
         }
-
-        None
     }
 
-    fn handle_rhai_result(&self, state: &mut TileEntityState, result: rhai::Array) {
-        if result.is_empty() {
-            return;
-        }
-
-        let ty: ImmutableString = result[0].clone().cast();
-
-        match ty.as_str() {
-            "make_transaction" => {
-                let coord: TileCoord = result[1].clone().cast();
-                let source_id: Id = result[2].clone().cast::<INT>().into();
-                let source_coord: TileCoord = result[3].clone().cast();
-
-                let stacks: Vec<ItemStack> = result[4]
-                    .clone()
-                    .try_cast()
-                    .unwrap_or_else(|| result[4].clone().into_typed_array().unwrap());
-
-                for stack in stacks {
-                    send_to_tile(
-                        state,
-                        coord,
-                        Transaction {
-                            stack,
-                            source_coord,
-                            source_id,
-                            root_coord: source_coord,
-                            root_id: source_id,
-                            hidden: false,
-                        },
-                    );
-                }
-            }
-            "make_hidden_transaction" => {
-                let coord: TileCoord = result[1].clone().cast();
-                let source_id: Id = result[2].clone().cast::<INT>().into();
-                let source_coord: TileCoord = result[3].clone().cast();
-
-                let stacks: Vec<ItemStack> = result[4]
-                    .clone()
-                    .try_cast()
-                    .unwrap_or_else(|| result[4].clone().into_typed_array().unwrap());
-
+    fn handle_rhai_result(
+        &self,
+        state: &mut TileEntityState,
+        function_id: &str,
+        action: TileAction,
+    ) {
+        match action {
+            TileAction::MakeTransaction {
+                coord,
+                source_id,
+                source_coord,
+                stacks,
+                hidden,
+            } => {
                 for stack in stacks {
                     send_to_tile(
                         state,
@@ -225,16 +280,16 @@ impl TileEntity {
                             source_id,
                             root_coord: source_coord,
                             root_id: source_id,
-                            hidden: true,
+                            hidden,
                         },
                     );
                 }
             }
-            "make_extract_request" => {
-                let coord: TileCoord = result[1].clone().cast();
-                let requested_from_id: Id = result[2].clone().cast::<INT>().into();
-                let requested_from_coord: TileCoord = result[3].clone().cast();
-
+            TileAction::MakeExtractRequest {
+                coord,
+                requested_from_id,
+                requested_from_coord,
+            } => {
                 send_to_tile(
                     state,
                     coord,
@@ -244,7 +299,11 @@ impl TileEntity {
                     },
                 );
             }
-            _ => (),
+            action => {
+                log::error!(
+                    "In {function_id}: {action:?} can only be returned from handle_transaction"
+                );
+            }
         }
     }
 
@@ -256,7 +315,7 @@ impl TileEntity {
         source_id: Id,
         root_coord: TileCoord,
         root_id: Id,
-    ) -> Option<GameMsg> {
+    ) -> Vec<GameMsg> {
         let tile = self.resource_man.registry.tiles.get(&self.id).unwrap();
 
         if let Some((ast, default_scope, function_id)) = tile
@@ -272,7 +331,7 @@ impl TileEntity {
             let mut rhai_state = Dynamic::from(data);
 
             let result = self.resource_man.engine.call_fn_with_options::<Dynamic>(
-                rhai_call_options(&mut rhai_state),
+                rhai_call_options(&mut rhai_state, &state.world),
                 scope,
                 ast,
                 "handle_transaction",
@@ -283,7 +342,7 @@ impl TileEntity {
                     ("source_id".into(), Dynamic::from_int(source_id.into())),
                     ("root_coord".into(), Dynamic::from(root_coord)),
                     ("root_id".into(), Dynamic::from_int(root_id.into())),
-                    ("random".into(), Dynamic::from_int(random())),
+                    ("random".into(), Dynamic::from_int(random(&mut state.rng))),
                     ("stack".into(), Dynamic::from(stack)),
                 ]),),
             );
@@ -292,23 +351,27 @@ impl TileEntity {
 
             match result {
                 Ok(result) => {
-                    if let Some(result) = result.try_cast::<rhai::Array>() {
-                        return self.handle_rhai_transaction_result(
-                            state,
-                            stack,
-                            source_coord,
-                            source_id,
-                            root_coord,
-                            root_id,
-                            result,
-                        );
-                    }
+                    return rhai_actions(function_id, result)
+                        .into_iter()
+                        .flat_map(|action| {
+                            self.handle_rhai_transaction_result(
+                                state,
+                                function_id,
+                                stack,
+                                source_coord,
+                                source_id,
+                                root_coord,
+                                root_id,
+                                action,
+                            )
+                        })
+                        .collect();
                 }
                 Err(err) => log_function_err(function_id, &err),
             }
         }
 
-        None
+        vec![]
     }
 }
 
@@ -316,14 +379,14 @@ impl TileEntity {
 impl Actor for TileEntity {
     type Msg = TileEntityMsg;
     type State = TileEntityState;
-    type Arguments = (ActorRef<GameMsg>,);
+    type Arguments = ();
 
     async fn pre_start(
         &self,
         _myself: ActorRef<Self::Msg>,
-        args: Self::Arguments,
+        _args: Self::Arguments,
     ) -> Result<Self::State, ActorProcessingErr> {
-        Ok(TileEntityState::new(args.0))
+        Ok(TileEntityState::new(tile_rng(self.seed, self.coord, 0)))
     }
 
     async fn handle(
@@ -333,9 +396,10 @@ impl Actor for TileEntity {
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
         match message {
-            Tick {
-                tick_count: _tick_count,
-            } => {
+            Tick { tick_count, world } => {
+                state.rng = tile_rng(self.seed, self.coord, tick_count);
+                state.world = Arc::downgrade(&world);
+
                 let tile = self.resource_man.registry.tiles.get(&self.id).unwrap();
 
                 if let Some((ast, default_scope, function_id)) = tile
@@ -351,14 +415,14 @@ impl Actor for TileEntity {
                     let mut rhai_state = Dynamic::from(data);
 
                     let result = self.resource_man.engine.call_fn_with_options::<Dynamic>(
-                        rhai_call_options(&mut rhai_state),
+                        rhai_call_options(&mut rhai_state, &state.world),
                         scope,
                         ast,
                         "handle_tick",
                         (rhai::Map::from([
                             ("coord".into(), Dynamic::from(self.coord)),
                             ("id".into(), Dynamic::from_int(self.id.into())),
-                            ("random".into(), Dynamic::from_int(random())),
+                            ("random".into(), Dynamic::from_int(random(&mut state.rng))),
                         ]),),
                     );
 
@@ -366,8 +430,8 @@ impl Actor for TileEntity {
 
                     match result {
                         Ok(result) => {
-                            if let Some(result) = result.try_cast::<rhai::Array>() {
-                                self.handle_rhai_result(state, result);
+                            for action in rhai_actions(function_id, result) {
+                                self.handle_rhai_result(state, function_id, action);
                             }
                         }
                         Err(err) => {
@@ -384,15 +448,21 @@ impl Actor for TileEntity {
                 root_id,
                 hidden,
             } => {
-                if let Some(record) =
-                    self.transaction(state, stack, source_coord, source_id, root_coord, root_id)
-                {
-                    if !hidden {
-                        state.game.send_message(record).unwrap();
-                    }
+                let records =
+                    self.transaction(state, stack, source_coord, source_id, root_coord, root_id);
+
+                if !hidden {
+                    state.outbox.extend(records);
                 }
             }
             TransactionResult { result } => {
+                let kind = if self.is_script_output(state, result) {
+                    StatisticKind::Produced
+                } else {
+                    StatisticKind::Moved
+                };
+                record_statistic(state, self.coord, result, kind);
+
                 let tile = self.resource_man.registry.tiles.get(&self.id).unwrap();
 
                 if let Some((ast, default_scope, function_id)) = tile
@@ -408,14 +478,14 @@ impl Actor for TileEntity {
                     let mut rhai_state = Dynamic::from(data);
 
                     let result = self.resource_man.engine.call_fn_with_options::<Dynamic>(
-                        rhai_call_options(&mut rhai_state),
+                        rhai_call_options(&mut rhai_state, &state.world),
                         scope,
                         ast,
                         "handle_transaction_result",
                         (rhai::Map::from([
                             ("coord".into(), Dynamic::from(self.coord)),
                             ("id".into(), Dynamic::from_int(self.id.into())),
-                            ("random".into(), Dynamic::from_int(random())),
+                            ("random".into(), Dynamic::from_int(random(&mut state.rng))),
                             ("transferred".into(), Dynamic::from(result)),
                         ]),),
                     );
@@ -432,11 +502,14 @@ impl Actor for TileEntity {
             }
             SetData(data) => {
                 state.data = RhaiDataMap::from_data_map(data);
+                state.data_replaced = true;
             }
             SetDataValue(key, value) => {
                 state.data.set(key, value);
             }
             TakeData(reply) => {
+                state.data_replaced = true;
+
                 reply
                     .send(mem::take(&mut state.data).to_data_map())
                     .unwrap();
@@ -455,6 +528,14 @@ impl Actor for TileEntity {
             RemoveData(key) => {
                 state.data.remove(key);
             }
+            TakeOutbox(reply) => {
+                // the game only fetches the data of the tiles that report a change for its next snapshot
+                if mem::take(&mut state.data_replaced) | state.data.take_changed() {
+                    state.outbox.push(GameMsg::TileDataChanged(self.coord));
+                }
+
+                reply.send(mem::take(&mut state.outbox)).unwrap();
+            }
             ExtractRequest {
                 requested_from_id,
                 requested_from_coord,
@@ -474,14 +555,14 @@ impl Actor for TileEntity {
                     let mut rhai_state = Dynamic::from(data);
 
                     let result = self.resource_man.engine.call_fn_with_options::<Dynamic>(
-                        rhai_call_options(&mut rhai_state),
+                        rhai_call_options(&mut rhai_state, &state.world),
                         scope,
                         ast,
                         "handle_extract_request",
                         (rhai::Map::from([
                             ("coord".into(), Dynamic::from(self.coord)),
                             ("id".into(), Dynamic::from_int(self.id.into())),
-                            ("random".into(), Dynamic::from_int(random())),
+                            ("random".into(), Dynamic::from_int(random(&mut state.rng))),
                             (
                                 "requested_from_coord".into(),
                                 Dynamic::from(requested_from_coord),
@@ -497,8 +578,8 @@ impl Actor for TileEntity {
 
                     match result {
                         Ok(result) => {
-                            if let Some(result) = result.try_cast::<rhai::Array>() {
-                                self.handle_rhai_result(state, result);
+                            for action in rhai_actions(function_id, result) {
+                                self.handle_rhai_result(state, function_id, action);
                             }
                         }
                         Err(err) => log_function_err(function_id, &err),
@@ -512,17 +593,20 @@ impl Actor for TileEntity {
 }
 
 fn send_to_tile(state: &mut TileEntityState, coord: TileCoord, message: TileEntityMsg) {
-    match state
-        .game
-        .send_message(GameMsg::ForwardMsgToTile(coord, message))
-    {
-        Ok(_) => {}
-        Err(_) => {
-            state.data = Default::default();
-        }
-    }
+    state.outbox.push(GameMsg::ForwardMsgToTile(coord, message));
+}
+
+fn record_statistic(
+    state: &mut TileEntityState,
+    coord: TileCoord,
+    stack: ItemStack,
+    kind: StatisticKind,
+) {
+    state
+        .outbox
+        .push(GameMsg::RecordStatistic(coord, stack, kind));
 }
 
-fn random() -> i32 {
-    thread_rng().next_u32() as i32
+fn random(rng: &mut TileRng) -> i32 {
+    rng.next_u32() as i32
 }
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use automancy_defs::id::Id;
+use automancy_resources::data::item::Item;
+use automancy_resources::kira::manager::backend::mock::MockBackend;
+use automancy_resources::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_resources::kira::track::TrackBuilder;
+use automancy_resources::types::tile::TileDef;
+use automancy_resources::ResourceManager;
+
+/// Held by the tests that set the global resource manager, which functions look scripts and tags up in,
+/// so that they cannot swap it out from under each other.
+pub static GLOBAL_RESOURCE_MAN: Mutex<()> = Mutex::const_new(());
+
+/// Makes an empty resource manager, with its audio track on a mock backend so that no audio device is needed.
+pub fn resource_man() -> ResourceManager {
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default()).unwrap();
+    let track = audio_man.add_sub_track(TrackBuilder::new()).unwrap();
+
+    ResourceManager::new(Arc::new(track))
+}
+
+/// Registers a tile without a model or a function.
+pub fn add_tile(resource_man: &mut ResourceManager, name: &str) -> Id {
+    let id = resource_man.interner.get_or_intern(name);
+
+    resource_man.registry.tiles.insert(
+        id,
+        TileDef {
+            model: resource_man.registry.none,
+            function: None,
+            data: Default::default(),
+        },
+    );
+
+    id
+}
+
+/// Registers an item without a model.
+pub fn add_item(resource_man: &mut ResourceManager, name: &str) -> Item {
+    let item = Item {
+        id: resource_man.interner.get_or_intern(name),
+        model: resource_man.registry.none,
+    };
+
+    resource_man.registry.items.insert(item.id, item);
+
+    item
+}
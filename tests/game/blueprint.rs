@@ -0,0 +1,173 @@
+use automancy::blueprint::{Blueprint, Orientation};
+use automancy_defs::coord::TileCoord;
+use automancy_resources::data::inventory::Inventory;
+use automancy_resources::data::{Data, DataMap};
+
+use crate::common::{add_item, add_tile, resource_man};
+
+#[test]
+fn test_blueprint_rotation() {
+    let resource_man = resource_man();
+
+    let id = resource_man.registry.none;
+    let target = resource_man.registry.data_ids.target;
+    let link = resource_man.registry.data_ids.link;
+
+    let origin = TileCoord::new(3, -1);
+    let mut data = DataMap::default();
+    data.insert(target, Data::Coord(TileCoord::RIGHT));
+    data.insert(link, Data::Coord(origin + TileCoord::RIGHT * 2));
+
+    let mut blueprint = Blueprint::new(
+        &resource_man,
+        vec![(origin + TileCoord::RIGHT, id, data)],
+        origin,
+    );
+
+    let (coord, _, data) = &blueprint.tiles[0];
+    assert_eq!(*coord, TileCoord::RIGHT);
+    assert_eq!(data.get(&link), Some(&Data::Coord(TileCoord::RIGHT * 2)));
+
+    blueprint.rotate(&resource_man, 3);
+
+    let (coord, _, data) = &blueprint.placed_at(&resource_man, origin)[0];
+    assert_eq!(*coord, origin + TileCoord::LEFT);
+    assert_eq!(data.get(&target), Some(&Data::Coord(TileCoord::LEFT)));
+    assert_eq!(
+        data.get(&link),
+        Some(&Data::Coord(origin + TileCoord::LEFT * 2))
+    );
+
+    blueprint.rotate(&resource_man, -3);
+
+    let (coord, _, data) = &blueprint.placed_at(&resource_man, origin)[0];
+    assert_eq!(*coord, origin + TileCoord::RIGHT);
+    assert_eq!(data.get(&target), Some(&Data::Coord(TileCoord::RIGHT)));
+}
+
+#[test]
+fn test_blueprint_mirror() {
+    let resource_man = resource_man();
+
+    assert_eq!(TileCoord::RIGHT.mirror(), TileCoord::RIGHT);
+    assert_eq!(TileCoord::TOP_RIGHT.mirror(), TileCoord::BOTTOM_RIGHT);
+    assert_eq!(TileCoord::TOP_LEFT.mirror(), TileCoord::BOTTOM_LEFT);
+
+    let coords = TileCoord::new(2, -3);
+    let mut data = DataMap::default();
+    data.insert(
+        resource_man.registry.data_ids.target,
+        Data::Coord(TileCoord::TOP_RIGHT),
+    );
+    data.insert(
+        resource_man.registry.data_ids.buffer,
+        Data::VecCoord(vec![TileCoord::TOP_LEFT, TileCoord::RIGHT]),
+    );
+
+    let original = Blueprint {
+        tiles: vec![(coords, resource_man.registry.none, data)],
+    };
+    let orientation = Orientation {
+        rotation: 2,
+        mirrored: true,
+    };
+
+    let mut blueprint = original.clone();
+    blueprint.mirror(&resource_man);
+
+    let (_, _, data) = &blueprint.tiles[0];
+    assert_eq!(
+        data.get(&resource_man.registry.data_ids.target),
+        Some(&Data::Coord(TileCoord::BOTTOM_RIGHT))
+    );
+    assert_eq!(
+        data.get(&resource_man.registry.data_ids.buffer),
+        Some(&Data::VecCoord(vec![
+            TileCoord::BOTTOM_LEFT,
+            TileCoord::RIGHT
+        ]))
+    );
+
+    for orientation in [
+        orientation,
+        Orientation {
+            rotation: 2,
+            mirrored: false,
+        },
+    ] {
+        let mut blueprint = original.clone();
+        blueprint.transform(&resource_man, orientation);
+        blueprint.transform(&resource_man, orientation.inverse());
+
+        assert_eq!(blueprint.tiles, original.tiles);
+    }
+}
+
+#[test]
+fn test_blueprint_export() {
+    let mut resource_man = resource_man();
+
+    let still = add_tile(&mut resource_man, "test:still");
+    let removed = resource_man.interner.get_or_intern("test:removed");
+
+    let mut data = DataMap::default();
+    data.insert(
+        resource_man.registry.data_ids.target,
+        Data::Coord(TileCoord::RIGHT),
+    );
+
+    let blueprint = Blueprint {
+        tiles: vec![
+            (TileCoord::ZERO, still, data.clone()),
+            (TileCoord::RIGHT, removed, DataMap::default()),
+        ],
+    };
+
+    let text = blueprint.export(&resource_man.interner).unwrap();
+    let imported = Blueprint::import(&resource_man, &text).unwrap();
+
+    assert_eq!(imported.tiles, vec![(TileCoord::ZERO, still, data)]);
+    assert_eq!(
+        resource_man.error_man.peek(),
+        Some((
+            resource_man.registry.err_ids.unknown_blueprint_ids,
+            vec!["test:removed".to_string()]
+        ))
+    );
+
+    assert!(Blueprint::import(&resource_man, "not a blueprint").is_err());
+}
+
+#[test]
+fn test_blueprint_unknown_data_ids() {
+    let mut resource_man = resource_man();
+    let still = add_tile(&mut resource_man, "test:still");
+
+    // the blueprint comes from a game with an item this one does not have
+    let mut other = crate::common::resource_man();
+    let other_still = add_tile(&mut other, "test:still");
+    let gone = add_item(&mut other, "test:gone");
+
+    let mut inventory = Inventory::default();
+    inventory.insert(gone.id, 3);
+
+    let mut data = DataMap::default();
+    data.insert(other.registry.data_ids.buffer, Data::Inventory(inventory));
+
+    let blueprint = Blueprint {
+        tiles: vec![(TileCoord::ZERO, other_still, data)],
+    };
+
+    let text = blueprint.export(&other.interner).unwrap();
+    let imported = Blueprint::import(&resource_man, &text).unwrap();
+
+    assert_eq!(imported.tiles.len(), 1);
+    assert_eq!(imported.tiles[0].1, still);
+    assert_eq!(
+        resource_man.error_man.peek(),
+        Some((
+            resource_man.registry.err_ids.unknown_blueprint_ids,
+            vec!["test:gone".to_string()]
+        ))
+    );
+}
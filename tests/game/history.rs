@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use ractor::ActorRef;
+
+use automancy::game::{GameMsg, PlaceBlueprintResponse};
+use automancy::setup::spawn_game;
+use automancy::tile_entity::TileEntityMsg;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+use automancy_resources::data::{Data, DataMap};
+
+use crate::common::{add_tile, resource_man};
+
+/// Gets the tile at the position, along with its target.
+async fn tile_at(
+    game: &ActorRef<GameMsg>,
+    target: Id,
+    coord: TileCoord,
+) -> Option<(Id, Option<Data>)> {
+    let id = game
+        .call(|reply| GameMsg::GetTile(coord, reply), None)
+        .await
+        .unwrap()
+        .unwrap()?;
+    let entity = game
+        .call(|reply| GameMsg::GetTileEntity(coord, reply), None)
+        .await
+        .unwrap()
+        .unwrap()?;
+
+    let data = entity
+        .call(|reply| TileEntityMsg::GetDataValue(target, reply), None)
+        .await
+        .unwrap()
+        .unwrap();
+
+    Some((id, data))
+}
+
+#[tokio::test]
+async fn test_undo_redo() {
+    let mut resource_man = resource_man();
+
+    let block = add_tile(&mut resource_man, "test:block");
+    let target = resource_man.registry.data_ids.target;
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    game.send_message(GameMsg::PlaceTile {
+        coord: TileCoord::ZERO,
+        id: block,
+        data: None,
+        record: true,
+        reply: None,
+    })
+    .unwrap();
+    game.send_message(GameMsg::SetTileData {
+        coord: TileCoord::ZERO,
+        values: vec![(target, Some(Data::Coord(TileCoord::LEFT)))],
+        record: true,
+    })
+    .unwrap();
+    game.send_message(GameMsg::MoveTiles(
+        vec![TileCoord::ZERO],
+        TileCoord::RIGHT,
+        true,
+    ))
+    .unwrap();
+
+    let moved = Some((block, Some(Data::Coord(TileCoord::LEFT))));
+    assert_eq!(tile_at(&game, target, TileCoord::RIGHT).await, moved);
+
+    game.send_message(GameMsg::Undo).unwrap();
+    assert_eq!(tile_at(&game, target, TileCoord::RIGHT).await, None);
+    assert_eq!(tile_at(&game, target, TileCoord::ZERO).await, moved);
+
+    game.send_message(GameMsg::Undo).unwrap();
+    assert_eq!(
+        tile_at(&game, target, TileCoord::ZERO).await,
+        Some((block, None))
+    );
+
+    game.send_message(GameMsg::Undo).unwrap();
+    assert_eq!(tile_at(&game, target, TileCoord::ZERO).await, None);
+
+    for _ in 0..3 {
+        game.send_message(GameMsg::Redo).unwrap();
+    }
+    assert_eq!(tile_at(&game, target, TileCoord::ZERO).await, None);
+    assert_eq!(tile_at(&game, target, TileCoord::RIGHT).await, moved);
+
+    // a new change clears what can be redone
+    game.send_message(GameMsg::Undo).unwrap();
+    game.send_message(GameMsg::SetTileData {
+        coord: TileCoord::ZERO,
+        values: vec![(target, None)],
+        record: true,
+    })
+    .unwrap();
+    game.send_message(GameMsg::Redo).unwrap();
+    assert_eq!(
+        tile_at(&game, target, TileCoord::ZERO).await,
+        Some((block, None))
+    );
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_recorded_history_undoes_whole_edit() {
+    let mut resource_man = resource_man();
+
+    let block = add_tile(&mut resource_man, "test:block");
+    let target = resource_man.registry.data_ids.target;
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    game.send_message(GameMsg::PlaceTile {
+        coord: TileCoord::ZERO,
+        id: block,
+        data: None,
+        record: false,
+        reply: None,
+    })
+    .unwrap();
+
+    // an edit made of several unrecorded changes, recorded once it is done
+    for dir in [TileCoord::LEFT, TileCoord::RIGHT] {
+        game.send_message(GameMsg::SetTileData {
+            coord: TileCoord::ZERO,
+            values: vec![(target, Some(Data::Coord(dir)))],
+            record: false,
+        })
+        .unwrap();
+    }
+    game.send_message(GameMsg::RecordHistory(vec![GameMsg::SetTileData {
+        coord: TileCoord::ZERO,
+        values: vec![(target, None)],
+        record: true,
+    }]))
+    .unwrap();
+
+    game.send_message(GameMsg::Undo).unwrap();
+    assert_eq!(
+        tile_at(&game, target, TileCoord::ZERO).await,
+        Some((block, None))
+    );
+
+    game.send_message(GameMsg::Redo).unwrap();
+    assert_eq!(
+        tile_at(&game, target, TileCoord::ZERO).await,
+        Some((block, Some(Data::Coord(TileCoord::RIGHT))))
+    );
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_blueprint_is_placed_whole() {
+    let mut resource_man = resource_man();
+
+    let block = add_tile(&mut resource_man, "test:block");
+    let missing = resource_man.interner.get_or_intern("test:missing");
+    let target = resource_man.registry.data_ids.target;
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    let place = |tiles| {
+        game.call(
+            |reply| GameMsg::PlaceBlueprint(tiles, true, Some(reply)),
+            None,
+        )
+    };
+
+    // one unknown tile keeps every other tile from being placed
+    let response = place(vec![
+        (TileCoord::ZERO, block, DataMap::default()),
+        (TileCoord::RIGHT, missing, DataMap::default()),
+    ])
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        response,
+        PlaceBlueprintResponse::UnknownTiles(vec![missing])
+    );
+    assert_eq!(tile_at(&game, target, TileCoord::ZERO).await, None);
+
+    let response = place(vec![
+        (TileCoord::ZERO, block, DataMap::default()),
+        (TileCoord::RIGHT, block, DataMap::default()),
+    ])
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(response, PlaceBlueprintResponse::Placed);
+    assert_eq!(
+        tile_at(&game, target, TileCoord::RIGHT).await,
+        Some((block, None))
+    );
+
+    // the whole blueprint is a single undo step
+    game.send_message(GameMsg::Undo).unwrap();
+    assert_eq!(tile_at(&game, target, TileCoord::ZERO).await, None);
+    assert_eq!(tile_at(&game, target, TileCoord::RIGHT).await, None);
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
@@ -0,0 +1,5 @@
+pub mod blueprint;
+pub mod history;
+pub mod reload;
+pub mod statistics;
+pub mod tick;
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use automancy::game::GameMsg;
+use automancy::setup::spawn_game;
+use automancy::tile_entity::TileEntityMsg;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+use crate::common::{add_tile, GLOBAL_RESOURCE_MAN};
+
+/// Makes a resource manager with the given tiles and the `test:count` data key,
+/// interning the padding first so that the IDs differ between them.
+fn resource_man(padding: &[&str], tiles: &[&str]) -> ResourceManager {
+    let mut resource_man = crate::common::resource_man();
+
+    for name in padding {
+        resource_man.interner.get_or_intern(name);
+    }
+
+    for name in tiles {
+        add_tile(&mut resource_man, name);
+    }
+
+    resource_man.interner.get_or_intern("test:count");
+
+    resource_man
+}
+
+fn get(resource_man: &ResourceManager, name: &str) -> Id {
+    resource_man.interner.get(name).unwrap()
+}
+
+#[tokio::test]
+async fn test_reload_keeps_tiles_and_data() {
+    // reloading sets the global resource manager
+    let _global = GLOBAL_RESOURCE_MAN.lock().await;
+
+    let old = Arc::new(resource_man(&[], &["test:block", "test:gone"]));
+    let new = Arc::new(resource_man(&["test:padding"], &["test:block"]));
+
+    let (game, game_handle) = spawn_game(old.clone()).await.unwrap();
+
+    let mut data = DataMap::default();
+    data.insert(get(&old, "test:count"), Data::Amount(2));
+
+    for (coord, id, data) in [
+        (TileCoord::ZERO, get(&old, "test:block"), Some(data)),
+        (TileCoord::RIGHT, get(&old, "test:gone"), None),
+    ] {
+        game.call(
+            |reply| GameMsg::PlaceTile {
+                coord,
+                id,
+                data,
+                record: false,
+                reply: Some(reply),
+            },
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    game.call(|reply| GameMsg::ReloadResources(new.clone(), reply), None)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let tile = game
+        .call(|reply| GameMsg::GetTile(TileCoord::ZERO, reply), None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(tile, Some(get(&new, "test:block")));
+    assert_ne!(get(&new, "test:block"), get(&old, "test:block"));
+
+    let tile_entity = game
+        .call(|reply| GameMsg::GetTileEntity(TileCoord::ZERO, reply), None)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let data = tile_entity
+        .call(TileEntityMsg::GetData, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(data.get(&get(&new, "test:count")), Some(&Data::Amount(2)));
+
+    // the tile that no longer exists is kept as a placeholder
+    let tile = game
+        .call(|reply| GameMsg::GetTile(TileCoord::RIGHT, reply), None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(tile, Some(new.registry.placeholder));
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
@@ -0,0 +1,48 @@
+use hashbrown::HashMap;
+
+use automancy::game::TPS;
+use automancy::statistics::{StatisticKind, StatisticWindow, Statistics};
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+use automancy_resources::data::item::Item;
+use automancy_resources::data::stack::ItemStack;
+
+#[test]
+fn test_statistics_windows() {
+    let item = Item {
+        id: Id::from(1),
+        model: Id::from(0),
+    };
+    let tiles = HashMap::from([(TileCoord::ZERO, Id::from(2))]);
+
+    let mut statistics = Statistics::default();
+
+    for _ in 0..TPS * 2 {
+        statistics.record(
+            TileCoord::ZERO,
+            ItemStack { item, amount: 1 },
+            StatisticKind::Produced,
+        );
+        statistics.record(
+            TileCoord::RIGHT,
+            ItemStack { item, amount: 1 },
+            StatisticKind::Consumed,
+        );
+        statistics.end_tick();
+    }
+
+    let second = statistics.report(StatisticWindow::Second, &tiles);
+    assert_eq!(second.items[&item.id][StatisticKind::Produced], TPS);
+    assert_eq!(second.items[&item.id][StatisticKind::Consumed], TPS);
+
+    let minute = statistics.report(StatisticWindow::Minute, &tiles);
+    assert_eq!(minute.items[&item.id][StatisticKind::Produced], TPS * 2);
+    assert_eq!(minute.items[&item.id][StatisticKind::Moved], 0);
+
+    let hour = statistics.report(StatisticWindow::Hour, &tiles);
+    assert_eq!(hour.items[&item.id][StatisticKind::Produced], TPS * 2);
+
+    // the tile at RIGHT is not on the map anymore
+    assert_eq!(hour.tiles.len(), 1);
+    assert_eq!(hour.tiles[&TileCoord::ZERO].0, Id::from(2));
+}
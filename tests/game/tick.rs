@@ -0,0 +1,488 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use hashbrown::HashSet;
+use ractor::ActorRef;
+use rhai::Scope;
+
+use automancy::game::{GameMsg, MAX_TRANSACTION_WAVES};
+use automancy::setup::spawn_game;
+use automancy::statistics::{StatisticKind, StatisticWindow};
+use automancy::tile_entity::TileEntityMsg;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::ItemStack;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::types::script::{Instructions, Script};
+use automancy_resources::types::tag::Tag;
+use automancy_resources::{ResourceManager, RESOURCE_MAN};
+
+use crate::common::{add_item, resource_man, GLOBAL_RESOURCE_MAN};
+
+static SOURCE: &str = r#"
+fn handle_tick(input) {
+    MakeTransaction(input.coord + TileCoord::RIGHT, input.id, input.coord, [ItemStack(ITEM, 1)])
+}
+"#;
+
+static SINK: &str = r#"
+fn handle_transaction(input) {
+    let count = this[COUNT];
+    if count == () {
+        count = 0;
+    }
+    this[COUNT] = count + input.stack.amount;
+
+    Consume(input.stack.amount)
+}
+"#;
+
+static DISTRIBUTOR: &str = r#"
+fn handle_tick(input) {
+    [
+        MakeTransaction(input.coord + TileCoord::RIGHT, input.id, input.coord, [ItemStack(ITEM, 1)]),
+        MakeTransaction(input.coord + TileCoord::LEFT, input.id, input.coord, [ItemStack(ITEM, 2)]),
+    ]
+}
+"#;
+
+static ONCE: &str = r#"
+fn handle_tick(input) {
+    if this[COUNT] == () {
+        this[COUNT] = 0;
+
+        MakeTransaction(input.coord + TileCoord::RIGHT, input.id, input.coord, [ItemStack(ITEM, 1)])
+    }
+}
+"#;
+
+static BOUNCER: &str = r#"
+fn handle_transaction(input) {
+    let count = this[COUNT];
+    if count == () {
+        count = 0;
+    }
+    this[COUNT] = count + 1;
+
+    if input.coord == FIRST {
+        PassOn(input.coord + TileCoord::RIGHT)
+    } else {
+        PassOn(input.coord + TileCoord::LEFT)
+    }
+}
+"#;
+
+static COUNTER: &str = r#"
+fn handle_tick(input) {
+    let count = this[COUNT];
+    if count == () {
+        count = 0;
+    }
+    this[COUNT] = count + 1;
+}
+"#;
+
+static WATCHER: &str = r#"
+fn handle_tick(input) {
+    let seen = data_of(input.coord + TileCoord::LEFT, COUNT);
+    if seen != () {
+        this[SEEN] = seen;
+    }
+}
+"#;
+
+fn add_tile(
+    resource_man: &mut ResourceManager,
+    name: &str,
+    source: &str,
+    scope: Scope<'static>,
+) -> Id {
+    let ast = resource_man.engine.compile(source).unwrap();
+    let id = crate::common::add_tile(resource_man, &format!("test:{name}"));
+
+    resource_man
+        .functions
+        .insert(id, (ast, scope, name.to_string()));
+    resource_man.registry.tiles.get_mut(&id).unwrap().function = Some(id);
+
+    id
+}
+
+async fn sink_count(game: &ActorRef<GameMsg>, coord: TileCoord, count: Id) -> Option<Data> {
+    let sink_entity = game
+        .call(|reply| GameMsg::GetTileEntity(coord, reply), None)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let data = sink_entity
+        .call(TileEntityMsg::GetData, None)
+        .await
+        .unwrap()
+        .unwrap();
+
+    data.get(&count).cloned()
+}
+
+/// Places a tile running `source` in the middle, with sinks on both sides of it, and ticks the game 3 times.
+/// Returns the amount of items taken by the sinks on the right and on the left.
+async fn run_source(source: &str) -> (Option<Data>, Option<Data>) {
+    let mut resource_man = resource_man();
+
+    let item = add_item(&mut resource_man, "test:item");
+    let count = resource_man.interner.get_or_intern("test:count");
+
+    let mut scope = Scope::new();
+    scope.push_constant("ITEM", item);
+    let source = add_tile(&mut resource_man, "source", source, scope);
+
+    let mut scope = Scope::new();
+    scope.push_constant("COUNT", count);
+    let sink = add_tile(&mut resource_man, "sink", SINK, scope);
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    for (coord, id) in [
+        (TileCoord::ZERO, source),
+        (TileCoord::RIGHT, sink),
+        (TileCoord::LEFT, sink),
+    ] {
+        game.call(
+            |reply| GameMsg::PlaceTile {
+                coord,
+                id,
+                data: None,
+                record: false,
+                reply: Some(reply),
+            },
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    for _ in 0..3 {
+        game.send_message(GameMsg::Tick).unwrap();
+    }
+
+    // every tick is fully handled before the game handles the next message
+    let counts = (
+        sink_count(&game, TileCoord::RIGHT, count).await,
+        sink_count(&game, TileCoord::LEFT, count).await,
+    );
+
+    game.stop(None);
+    game_handle.await.unwrap();
+
+    counts
+}
+
+#[tokio::test]
+async fn test_tick_completes_transactions() {
+    assert_eq!(run_source(SOURCE).await, (Some(Data::Amount(3)), None));
+}
+
+#[tokio::test]
+async fn test_tick_runs_every_action() {
+    assert_eq!(
+        run_source(DISTRIBUTOR).await,
+        (Some(Data::Amount(3)), Some(Data::Amount(6)))
+    );
+}
+
+#[tokio::test]
+async fn test_tick_keeps_messages_past_the_last_wave() {
+    let mut resource_man = resource_man();
+
+    let item = add_item(&mut resource_man, "test:item");
+    let count = resource_man.interner.get_or_intern("test:count");
+
+    let mut scope = Scope::new();
+    scope.push_constant("ITEM", item);
+    scope.push_constant("COUNT", count);
+    let source = add_tile(&mut resource_man, "source", ONCE, scope);
+
+    let mut scope = Scope::new();
+    scope.push_constant("COUNT", count);
+    scope.push_constant("FIRST", TileCoord::RIGHT);
+    let bouncer = add_tile(&mut resource_man, "bouncer", BOUNCER, scope);
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    // the item is passed back and forth between the two bouncers forever, once per wave
+    let bouncers = [TileCoord::RIGHT, TileCoord::RIGHT + TileCoord::RIGHT];
+
+    for (coord, id) in [
+        (TileCoord::ZERO, source),
+        (bouncers[0], bouncer),
+        (bouncers[1], bouncer),
+    ] {
+        game.call(
+            |reply| GameMsg::PlaceTile {
+                coord,
+                id,
+                data: None,
+                record: false,
+                reply: Some(reply),
+            },
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    for _ in 0..2 {
+        game.send_message(GameMsg::Tick).unwrap();
+    }
+
+    let mut bounces = 0;
+    for coord in bouncers {
+        if let Some(Data::Amount(amount)) = sink_count(&game, coord, count).await {
+            bounces += amount;
+        }
+    }
+
+    // what was left at the end of the first tick carries on in the second one
+    assert_eq!(bounces as usize, MAX_TRANSACTION_WAVES * 2);
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_snapshot_follows_changed_data() {
+    let mut resource_man = resource_man();
+
+    let count = resource_man.interner.get_or_intern("test:count");
+    let seen = resource_man.interner.get_or_intern("test:seen");
+
+    let mut scope = Scope::new();
+    scope.push_constant("COUNT", count);
+    let counter = add_tile(&mut resource_man, "counter", COUNTER, scope);
+
+    let mut scope = Scope::new();
+    scope.push_constant("COUNT", count);
+    scope.push_constant("SEEN", seen);
+    let watcher = add_tile(&mut resource_man, "watcher", WATCHER, scope);
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    for (coord, id) in [(TileCoord::ZERO, counter), (TileCoord::RIGHT, watcher)] {
+        game.call(
+            |reply| GameMsg::PlaceTile {
+                coord,
+                id,
+                data: None,
+                record: false,
+                reply: Some(reply),
+            },
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    for _ in 0..3 {
+        game.send_message(GameMsg::Tick).unwrap();
+    }
+
+    assert_eq!(
+        sink_count(&game, TileCoord::ZERO, count).await,
+        Some(Data::Amount(3))
+    );
+    // each tick sees the count as it was at the end of the one before
+    assert_eq!(
+        sink_count(&game, TileCoord::RIGHT, seen).await,
+        Some(Data::Amount(2))
+    );
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
+
+/// Places a tile at the coordinates, or removes the one there if `id` is none.
+async fn place(game: &ActorRef<GameMsg>, coord: TileCoord, id: Id, data: Option<DataMap>) {
+    game.call(
+        |reply| GameMsg::PlaceTile {
+            coord,
+            id,
+            data,
+            record: false,
+            reply: Some(reply),
+        },
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+}
+
+/// Ticks the game, and gets how many items the sink at the coordinates has taken.
+async fn tick_sink(
+    game: &ActorRef<GameMsg>,
+    ticks: usize,
+    coord: TileCoord,
+    count: Id,
+) -> Option<Data> {
+    for _ in 0..ticks {
+        game.send_message(GameMsg::Tick).unwrap();
+    }
+
+    sink_count(game, coord, count).await
+}
+
+#[tokio::test]
+async fn test_machine_needs_adjacent_tile() {
+    let mut resource_man = resource_man();
+    resource_man
+        .load_functions(Path::new("resources/automancy"))
+        .unwrap();
+
+    let item = add_item(&mut resource_man, "test:item");
+    let count = resource_man.interner.get_or_intern("test:count");
+
+    let heater = crate::common::add_tile(&mut resource_man, "test:heater");
+    let heat = resource_man.interner.get_or_intern("test:heat");
+    resource_man.registry.tags.insert(
+        heat,
+        Tag {
+            id: heat,
+            entries: HashSet::from([heater]),
+        },
+    );
+
+    // one script needs the heater itself next to the machine, and the other any tile tagged as heat
+    let mut scripts = vec![];
+    for (name, adjacent) in [("test:near_heater", heater), ("test:near_heat", heat)] {
+        let id = resource_man.interner.get_or_intern(name);
+
+        resource_man.registry.scripts.insert(
+            id,
+            Script {
+                id,
+                adjacent: Some(adjacent),
+                instructions: Instructions {
+                    inputs: None,
+                    outputs: vec![ItemStack { item, amount: 1 }],
+                },
+            },
+        );
+        scripts.push(id);
+    }
+
+    let machine = crate::common::add_tile(&mut resource_man, "test:machine");
+    resource_man
+        .registry
+        .tiles
+        .get_mut(&machine)
+        .unwrap()
+        .function = resource_man.interner.get("automancy:machine");
+
+    let mut scope = Scope::new();
+    scope.push_constant("COUNT", count);
+    let sink = add_tile(&mut resource_man, "sink", SINK, scope);
+
+    let script_key = resource_man.registry.data_ids.script;
+    let target_key = resource_man.registry.data_ids.target;
+    let none = resource_man.registry.none;
+    let resource_man = Arc::new(resource_man);
+
+    // the machine looks its script up in the global resource manager
+    let _global = GLOBAL_RESOURCE_MAN.lock().await;
+    RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
+
+    let (game, game_handle) = spawn_game(resource_man).await.unwrap();
+
+    let mut data = DataMap::default();
+    data.insert(script_key, Data::Id(scripts[0]));
+    data.insert(target_key, Data::Coord(TileCoord::RIGHT));
+
+    place(&game, TileCoord::ZERO, machine, Some(data)).await;
+    place(&game, TileCoord::RIGHT, sink, None).await;
+
+    // the sink next to the machine is not what the script needs
+    assert_eq!(tick_sink(&game, 2, TileCoord::RIGHT, count).await, None);
+
+    place(&game, TileCoord::LEFT, heater, None).await;
+    assert_eq!(
+        tick_sink(&game, 2, TileCoord::RIGHT, count).await,
+        Some(Data::Amount(2))
+    );
+
+    game.send_message(GameMsg::SetTileData {
+        coord: TileCoord::ZERO,
+        values: vec![(script_key, Some(Data::Id(scripts[1])))],
+        record: false,
+    })
+    .unwrap();
+    assert_eq!(
+        tick_sink(&game, 1, TileCoord::RIGHT, count).await,
+        Some(Data::Amount(3))
+    );
+
+    place(&game, TileCoord::LEFT, none, None).await;
+    assert_eq!(
+        tick_sink(&game, 2, TileCoord::RIGHT, count).await,
+        Some(Data::Amount(3))
+    );
+
+    // only what the machine made from its script counts as produced
+    let report = game
+        .call(
+            |reply| GameMsg::GetStatistics(StatisticWindow::Hour, reply),
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.items[&item.id][StatisticKind::Produced], 3);
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_sent_items_are_not_produced() {
+    let mut resource_man = resource_man();
+
+    let item = add_item(&mut resource_man, "test:item");
+    let count = resource_man.interner.get_or_intern("test:count");
+
+    let mut scope = Scope::new();
+    scope.push_constant("ITEM", item);
+    let source = add_tile(&mut resource_man, "source", SOURCE, scope);
+
+    let mut scope = Scope::new();
+    scope.push_constant("COUNT", count);
+    let sink = add_tile(&mut resource_man, "sink", SINK, scope);
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    place(&game, TileCoord::ZERO, source, None).await;
+    place(&game, TileCoord::RIGHT, sink, None).await;
+
+    assert_eq!(
+        tick_sink(&game, 3, TileCoord::RIGHT, count).await,
+        Some(Data::Amount(3))
+    );
+
+    // the source has no script, so it only sent the items along
+    let report = game
+        .call(
+            |reply| GameMsg::GetStatistics(StatisticWindow::Hour, reply),
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let counts = &report.items[&item.id];
+    assert_eq!(counts[StatisticKind::Produced], 0);
+    assert_eq!(counts[StatisticKind::Moved], 3);
+    assert_eq!(counts[StatisticKind::Consumed], 3);
+
+    game.stop(None);
+    game_handle.await.unwrap();
+}
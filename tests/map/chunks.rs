@@ -0,0 +1,97 @@
+use hashbrown::HashMap;
+
+use automancy::map::{chunk_of, MapCodec, MapRaw, MapSummary, CHUNK_SIZE};
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{id, Id};
+use automancy_resources::data::DataRaw;
+
+fn map() -> MapRaw {
+    let node = Id::from(0i64);
+    let data = HashMap::from([
+        (
+            "automancy:target".to_string(),
+            DataRaw::Coord(TileCoord::RIGHT),
+        ),
+        ("automancy:amount".to_string(), DataRaw::Amount(8)),
+    ]);
+
+    MapRaw {
+        map_name: "chunks".to_string(),
+        tiles: vec![
+            (TileCoord::ZERO, node, data.clone().into()),
+            (TileCoord::new(CHUNK_SIZE - 1, 0), node, data.clone().into()),
+            (TileCoord::new(CHUNK_SIZE, -1), node, data.into()),
+        ],
+        tile_map: HashMap::from([(node, id("automancy", "node"))]),
+    }
+}
+
+#[test]
+fn test_tiles_split_into_chunks() {
+    assert_eq!(chunk_of(TileCoord::new(-1, 0)), (-1, 0));
+
+    let chunks = map().into_chunks();
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[&(0, 0)].tiles.len(), 2);
+    assert_eq!(chunks[&(1, -1)].tiles.len(), 1);
+    assert_eq!(chunks[&(1, -1)].tile_map.len(), 1);
+}
+
+#[test]
+fn test_chunks_join_back() {
+    let map = map();
+
+    let joined = MapRaw::join(
+        map.map_name.clone(),
+        map.clone().into_chunks().into_values(),
+    );
+
+    assert_eq!(joined.tiles.len(), map.tiles.len());
+    assert_eq!(joined.tile_map.len(), 1);
+    assert_eq!(joined.content_hash(), map.content_hash());
+}
+
+#[test]
+fn test_content_hash_ignores_order_and_keys() {
+    let map = map();
+
+    let mut shuffled = map.clone();
+    shuffled.tiles.reverse();
+
+    // the same tiles saved under another key by another session
+    let key = Id::from(7i64);
+    shuffled.tile_map = HashMap::from([(key, id("automancy", "node"))]);
+    for (_, id, _) in shuffled.tiles.iter_mut() {
+        *id = key;
+    }
+
+    assert_eq!(map.content_hash(), shuffled.content_hash());
+
+    shuffled.tiles.pop();
+    assert_ne!(map.content_hash(), shuffled.content_hash());
+}
+
+#[test]
+fn test_codecs_round_trip() {
+    let map = map();
+
+    for codec in [MapCodec::Ron, MapCodec::Bincode] {
+        let encoded = map.encode(Vec::new(), codec).unwrap();
+        let decoded = MapRaw::decode(encoded.as_slice(), codec).unwrap();
+
+        assert_eq!(map.content_hash(), decoded.content_hash(), "{codec:?}");
+    }
+}
+
+#[test]
+fn test_summary_counts_tiles_across_chunks() {
+    let mut summary = MapSummary::default();
+
+    for chunk in map().into_chunks().into_values() {
+        summary.add(&chunk);
+    }
+
+    assert_eq!(summary.tiles.get(&id("automancy", "node")), Some(&3));
+    assert!(summary.items.is_empty());
+}
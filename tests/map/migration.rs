@@ -0,0 +1,58 @@
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+
+use zstd::Decoder;
+
+use automancy::map::{MapInfoRaw, MapRaw};
+use automancy::migration::{migrate, MAP_VERSION};
+use automancy_resources::data::DataRaw;
+
+/// The maps saved by older versions of the game, one directory each.
+static SAVES: &str = "tests/map/saves";
+
+fn read(dir: &Path) -> (MapInfoRaw, MapRaw) {
+    let info = ron::from_str(&fs::read_to_string(dir.join("header.ron")).unwrap()).unwrap();
+    let map = ron::de::from_reader(Decoder::new(File::open(dir.join("map.zst")).unwrap()).unwrap())
+        .unwrap();
+
+    (info, map)
+}
+
+#[test]
+fn test_old_saves_migrate() {
+    for dir in fs::read_dir(SAVES).unwrap() {
+        let dir = dir.unwrap().path();
+
+        let (mut info, mut map) = read(&dir);
+        let tile_count = map.tiles.len();
+
+        migrate(&mut info, &mut map).unwrap_or_else(|e| panic!("{dir:?}: {e:?}"));
+
+        assert_eq!(info.version, MAP_VERSION, "{dir:?}");
+        assert_eq!(map.tiles.len(), tile_count, "{dir:?}");
+        assert_eq!(info.tile_count as usize, tile_count, "{dir:?}");
+    }
+}
+
+#[test]
+fn test_unlocked_researches_migrate_to_set() {
+    let (mut info, mut map) = read(&Path::new(SAVES).join("v0_researches"));
+    assert_eq!(info.version, 0);
+
+    migrate(&mut info, &mut map).unwrap();
+
+    let data = info.data.into_inner();
+    assert!(matches!(
+        data.get("automancy:unlocked_researches"),
+        Some(DataRaw::SetId(ids)) if ids.len() == 2
+    ));
+}
+
+#[test]
+fn test_newer_map_is_rejected() {
+    let (mut info, mut map) = read(&Path::new(SAVES).join("v0_basic"));
+    info.version = MAP_VERSION + 1;
+
+    assert!(migrate(&mut info, &mut map).is_err());
+}
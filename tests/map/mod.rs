@@ -0,0 +1,4 @@
+pub mod chunks;
+pub mod migration;
+pub mod remap;
+pub mod save;
@@ -0,0 +1,86 @@
+use hashbrown::HashMap;
+
+use automancy::map::{MapInfoRaw, MapRaw};
+use automancy::remap::{collect_missing_ids, remap_ids, IdRemap, MissingIdKind};
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{id, Id, IdRaw};
+use automancy_resources::data::DataRaw;
+use automancy_resources::ResourceManager;
+
+use crate::common::{add_item, add_tile};
+
+fn resource_man() -> ResourceManager {
+    let mut resource_man = crate::common::resource_man();
+
+    add_tile(&mut resource_man, "test:block");
+    add_item(&mut resource_man, "test:ore");
+
+    resource_man
+}
+
+/// A map with a tile that still exists and one that was renamed, which wants an item that was renamed.
+fn map() -> (MapInfoRaw, MapRaw) {
+    let info = MapInfoRaw {
+        version: 0,
+        tile_count: 2,
+        codec: Default::default(),
+        description: Default::default(),
+        play_time: 0,
+        tick_count: 0,
+        summary: Default::default(),
+        data: Default::default(),
+    };
+
+    let data = HashMap::from([(
+        "automancy:item".to_string(),
+        DataRaw::Id(id("test", "old_ore")),
+    )]);
+
+    let map = MapRaw {
+        map_name: "remap".to_string(),
+        tiles: vec![
+            (TileCoord::ZERO, Id::from(0i64), Default::default()),
+            (TileCoord::RIGHT, Id::from(1i64), data.into()),
+        ],
+        tile_map: HashMap::from([
+            (Id::from(0i64), id("test", "block")),
+            (Id::from(1i64), id("test", "old_block")),
+        ]),
+    };
+
+    (info, map)
+}
+
+#[test]
+fn test_missing_ids_are_collected() {
+    let resource_man = resource_man();
+    let (info, map) = map();
+
+    let missing = collect_missing_ids(&resource_man, &info, &map);
+
+    assert_eq!(
+        missing.into_iter().collect::<Vec<_>>(),
+        vec![
+            (id("test", "old_block"), MissingIdKind::Tile),
+            (id("test", "old_ore"), MissingIdKind::Item),
+        ]
+    );
+}
+
+#[test]
+fn test_missing_ids_are_remapped() {
+    let resource_man = resource_man();
+    let (mut info, mut map) = map();
+
+    let remap = IdRemap::from([
+        (id("test", "old_block"), id("test", "block")),
+        (id("test", "old_ore"), id("test", "ore")),
+    ]);
+    remap_ids(&mut info, &mut map, &remap);
+
+    assert!(collect_missing_ids(&resource_man, &info, &map).is_empty());
+    assert!(matches!(
+        map.tiles[1].2.get("automancy:item"),
+        Some(DataRaw::Id(ore)) if *ore == IdRaw::parse("test:ore")
+    ));
+}
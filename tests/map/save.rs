@@ -0,0 +1,344 @@
+use std::fs;
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use ractor::Actor;
+
+use automancy::game::{Game, GameMsg};
+use automancy::map::{
+    chunk_of, Map, MapInfoRaw, MapRaw, BACKUP_PATH, CHUNK_PATH, CHUNK_SIZE, MAP_EXT,
+};
+use automancy::migration::MAP_VERSION;
+use automancy::setup::spawn_game;
+use automancy::tile_entity::TileEntity;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{id, Id};
+use automancy_resources::data::DataRaw;
+
+use crate::common::{add_tile, resource_man};
+
+/// Keeps the maps of the tests in a temporary folder, rather than among the maps of the working directory.
+fn use_temp_root() {
+    Map::set_root(std::env::temp_dir().join("automancy_test_maps"));
+}
+
+fn info() -> MapInfoRaw {
+    MapInfoRaw {
+        version: MAP_VERSION,
+        tile_count: 0,
+        codec: Default::default(),
+        description: Default::default(),
+        play_time: 0,
+        tick_count: 0,
+        summary: Default::default(),
+        data: Default::default(),
+    }
+}
+
+/// Writes a map with a tile that exists and one that does not, which has some data.
+fn write_map(map_name: &str) {
+    use_temp_root();
+    drop(fs::remove_dir_all(Map::path(map_name)));
+
+    let data = HashMap::from([("test:count".to_string(), DataRaw::Amount(5))]);
+
+    let map = MapRaw {
+        map_name: map_name.to_string(),
+        tiles: vec![
+            (TileCoord::ZERO, Id::from(0i64), data.into()),
+            (TileCoord::LEFT, Id::from(1i64), Default::default()),
+        ],
+        tile_map: HashMap::from([
+            (Id::from(0i64), id("test", "gone")),
+            (Id::from(1i64), id("test", "block")),
+        ]),
+    };
+
+    Map::replace_tiles(map_name, info(), map).unwrap();
+}
+
+/// Reads the tiles of a saved map, by their position.
+fn read_map(map_name: &str) -> HashMap<TileCoord, (String, Option<DataRaw>)> {
+    let saved = Map::read_header(map_name).unwrap();
+    let map = Map::read_all_tiles(map_name, &saved).unwrap();
+
+    map.tiles
+        .into_iter()
+        .map(|(coord, id, data)| {
+            (
+                coord,
+                (
+                    map.tile_map[&id].to_string(),
+                    data.get("test:count").cloned(),
+                ),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_moved_placeholder_is_saved() {
+    let map_name = "test_moved_placeholder";
+    write_map(map_name);
+
+    let mut resource_man = resource_man();
+    add_tile(&mut resource_man, "test:block");
+    resource_man.interner.get_or_intern("test:count");
+
+    let (game, game_handle) = spawn_game(Arc::new(resource_man)).await.unwrap();
+
+    game.send_message(GameMsg::LoadMap(map_name.to_string(), Default::default()))
+        .unwrap();
+    game.send_message(GameMsg::MoveTiles(
+        vec![TileCoord::ZERO],
+        TileCoord::RIGHT,
+        true,
+    ))
+    .unwrap();
+    game.call(GameMsg::SaveMap, None).await.unwrap().unwrap();
+
+    let tiles = read_map(map_name);
+
+    assert_eq!(tiles.len(), 2);
+    assert!(matches!(
+        &tiles[&TileCoord::RIGHT],
+        (id, Some(DataRaw::Amount(5))) if id == "test:gone"
+    ));
+    assert_eq!(tiles[&TileCoord::LEFT].0, "test:block");
+
+    game.stop(None);
+    game_handle.await.unwrap();
+
+    fs::remove_dir_all(Map::path(map_name)).unwrap();
+}
+
+#[tokio::test]
+async fn test_save_reports_dead_tiles() {
+    let map_name = "test_save_dead_tiles";
+    use_temp_root();
+    drop(fs::remove_dir_all(Map::path(map_name)));
+
+    let mut resource_man = resource_man();
+    let block = add_tile(&mut resource_man, "test:block");
+    let resource_man = Arc::new(resource_man);
+
+    let (tile_entity, handle) = Actor::spawn(
+        None,
+        TileEntity {
+            id: block,
+            coord: TileCoord::ZERO,
+            seed: 0,
+            resource_man: resource_man.clone(),
+        },
+        (),
+    )
+    .await
+    .unwrap();
+
+    tile_entity.stop(None);
+    handle.await.unwrap();
+
+    let mut map = Map::new_empty(map_name.to_string());
+    map.tiles.insert(TileCoord::ZERO, block);
+
+    let tile_entities = HashMap::from([(TileCoord::ZERO, tile_entity)]);
+
+    assert!(map.save(&resource_man, &tile_entities).await.is_err());
+    // the header is only written once all the tiles are
+    assert!(Map::read_header(map_name).is_err());
+
+    drop(fs::remove_dir_all(Map::path(map_name)));
+}
+
+#[test]
+fn test_backup_is_restored() {
+    let map_name = "test_backup_restored";
+    write_map(map_name);
+
+    // a backup slot is laid out like the map itself
+    let backup = Map::backup(map_name, 0);
+    fs::create_dir_all(backup.join(CHUNK_PATH)).unwrap();
+    fs::copy(Map::info(map_name), backup.join("header.ron")).unwrap();
+    for entry in fs::read_dir(Map::chunks(map_name)).unwrap() {
+        let entry = entry.unwrap();
+
+        fs::copy(
+            entry.path(),
+            backup.join(CHUNK_PATH).join(entry.file_name()),
+        )
+        .unwrap();
+    }
+
+    Map::replace_tiles(map_name, info(), MapRaw::default()).unwrap();
+    assert!(read_map(map_name).is_empty());
+
+    Map::restore_backup(map_name, 0).unwrap();
+
+    let tiles = read_map(map_name);
+    assert_eq!(tiles.len(), 2);
+    assert_eq!(tiles[&TileCoord::LEFT].0, "test:block");
+
+    // nothing is left over from the swap
+    let mut files = fs::read_dir(Map::path(map_name))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(files, vec![BACKUP_PATH, CHUNK_PATH, "header.ron"]);
+
+    fs::remove_dir_all(Map::path(map_name)).unwrap();
+}
+
+#[test]
+fn test_interrupted_swap_is_recovered() {
+    let map_name = "test_interrupted_swap";
+    write_map(map_name);
+
+    // the game stopped after moving the old chunks out of the way, but before the new ones were moved in
+    fs::rename(
+        Map::chunks(map_name),
+        Map::path(map_name).join(format!("{CHUNK_PATH}.old")),
+    )
+    .unwrap();
+
+    assert_eq!(read_map(map_name).len(), 2);
+
+    fs::remove_dir_all(Map::path(map_name)).unwrap();
+}
+
+#[tokio::test]
+async fn test_unreadable_chunk_fails_the_map() {
+    let map_name = "test_unreadable_chunk";
+    write_map(map_name);
+
+    let (q, r) = chunk_of(TileCoord::ZERO);
+    let chunk = Map::chunks(map_name).join(format!("{q}_{r}{MAP_EXT}"));
+    fs::write(&chunk, b"not a chunk").unwrap();
+
+    let resource_man = Arc::new(resource_man());
+    let (game, game_handle) = Actor::spawn(None, Game, resource_man.clone())
+        .await
+        .unwrap();
+
+    let (map, tile_entities) =
+        Map::load(game.clone(), resource_man, map_name, &Default::default()).await;
+
+    // the session goes somewhere else, so that saving it cannot overwrite the map
+    assert!(map.map_name.starts_with(&format!("{map_name}-ERR-")));
+    assert!(tile_entities.is_empty());
+    assert_eq!(fs::read(&chunk).unwrap(), b"not a chunk");
+    assert_eq!(fs::read_dir(Map::chunks(map_name)).unwrap().count(), 2);
+
+    game.stop(None);
+    game_handle.await.unwrap();
+
+    fs::remove_dir_all(Map::path(map_name)).unwrap();
+}
+
+#[tokio::test]
+async fn test_backup_is_written_into_next_slot() {
+    let map_name = "test_backup_next_slot";
+    write_map(map_name);
+
+    let mut resource_man = resource_man();
+    add_tile(&mut resource_man, "test:block");
+    let resource_man = Arc::new(resource_man);
+
+    let (game, game_handle) = Actor::spawn(None, Game, resource_man.clone())
+        .await
+        .unwrap();
+
+    let (map, tile_entities) = Map::load(
+        game.clone(),
+        resource_man.clone(),
+        map_name,
+        &Default::default(),
+    )
+    .await;
+
+    for _ in 0..2 {
+        map.snapshot_backup(&resource_man, &tile_entities)
+            .await
+            .unwrap()
+            .write_backup()
+            .unwrap();
+    }
+
+    // each backup takes a slot of its own, and nothing is left over from writing them
+    let mut slots = fs::read_dir(Map::backups(map_name))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    slots.sort();
+    assert_eq!(slots, vec!["0", "1"]);
+    assert_eq!(Map::list_backups(map_name).len(), 2);
+
+    game.stop(None);
+    game_handle.await.unwrap();
+
+    fs::remove_dir_all(Map::path(map_name)).unwrap();
+}
+
+#[tokio::test]
+async fn test_only_dirty_chunks_are_saved() {
+    let map_name = "test_dirty_chunks";
+    use_temp_root();
+    drop(fs::remove_dir_all(Map::path(map_name)));
+
+    let far = TileCoord::new(CHUNK_SIZE, 0);
+    let map = MapRaw {
+        map_name: map_name.to_string(),
+        tiles: vec![
+            (TileCoord::ZERO, Id::from(0i64), Default::default()),
+            (far, Id::from(0i64), Default::default()),
+        ],
+        tile_map: HashMap::from([(Id::from(0i64), id("test", "block"))]),
+    };
+    Map::replace_tiles(map_name, info(), map).unwrap();
+
+    let mut resource_man = resource_man();
+    let block = add_tile(&mut resource_man, "test:block");
+    let resource_man = Arc::new(resource_man);
+
+    let (game, game_handle) = Actor::spawn(None, Game, resource_man.clone())
+        .await
+        .unwrap();
+
+    let (mut map, mut tile_entities) = Map::load(
+        game.clone(),
+        resource_man.clone(),
+        map_name,
+        &Default::default(),
+    )
+    .await;
+
+    // the far tile cannot send its data, so asking it would fail the save
+    let (dead, handle) = Actor::spawn(
+        None,
+        TileEntity {
+            id: block,
+            coord: far,
+            seed: 0,
+            resource_man: resource_man.clone(),
+        },
+        (),
+    )
+    .await
+    .unwrap();
+    dead.stop(None);
+    handle.await.unwrap();
+    tile_entities.insert(far, dead);
+
+    map.dirty_chunks.insert(chunk_of(TileCoord::ZERO));
+    map.save(&resource_man, &tile_entities).await.unwrap();
+    assert!(map.dirty_chunks.is_empty());
+    assert_eq!(read_map(map_name).len(), 2);
+
+    map.dirty_chunks.insert(chunk_of(far));
+    assert!(map.save(&resource_man, &tile_entities).await.is_err());
+
+    game.stop(None);
+    game_handle.await.unwrap();
+
+    fs::remove_dir_all(Map::path(map_name)).unwrap();
+}
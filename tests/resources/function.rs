@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use rhai::{CallFnOptions, Dynamic, Scope, INT};
+
+use automancy_defs::coord::TileCoord;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::types::function::{TileAction, WorldSnapshot};
+
+use crate::common::{add_item, resource_man};
+
+#[test]
+fn test_actions_are_constructed() {
+    let mut resource_man = resource_man();
+
+    let item = add_item(&mut resource_man, "test:item");
+
+    let mut scope = Scope::new();
+    scope.push_constant("ITEM", item);
+
+    let action = resource_man
+        .engine
+        .eval_with_scope::<TileAction>(
+            &mut scope,
+            "MakeHiddenTransaction(TileCoord::RIGHT, 1, TileCoord::ZERO, [ItemStack(ITEM, 2)])",
+        )
+        .unwrap();
+
+    assert!(matches!(
+        action,
+        TileAction::MakeTransaction { coord, stacks, hidden: true, .. }
+            if coord == TileCoord::RIGHT && stacks.len() == 1 && stacks[0].amount == 2
+    ));
+
+    let action = resource_man
+        .engine
+        .eval::<TileAction>("PassOn(TileCoord::LEFT)")
+        .unwrap();
+
+    assert!(matches!(action, TileAction::PassOn(coord) if coord == TileCoord::LEFT));
+}
+
+#[test]
+fn test_malformed_stacks_are_an_error() {
+    let resource_man = resource_man();
+
+    let result = resource_man.engine.eval::<TileAction>(
+        r#"MakeTransaction(TileCoord::RIGHT, 1, TileCoord::ZERO, ["not a stack"])"#,
+    );
+
+    assert!(result.is_err());
+}
+
+static QUERY: &str = r#"
+fn query(coord) {
+    [tile_at(coord), data_of(coord, KEY), neighbors(TileCoord::ZERO)]
+}
+"#;
+
+#[test]
+fn test_world_is_queried() {
+    let mut resource_man = resource_man();
+
+    let tile = resource_man.interner.get_or_intern("test:tile");
+    let key = resource_man.interner.get_or_intern("test:key");
+
+    let mut data = DataMap::default();
+    data.insert(key, Data::Amount(4));
+
+    let world = Arc::new(WorldSnapshot {
+        tiles: HashMap::from([(TileCoord::ZERO, tile), (TileCoord::RIGHT, tile)]),
+        data: HashMap::from([(TileCoord::RIGHT, Arc::new(data))]),
+    });
+
+    let ast = resource_man.engine.compile(QUERY).unwrap();
+    let mut scope = Scope::new();
+    scope.push_constant("KEY", key);
+
+    let result = resource_man
+        .engine
+        .call_fn_with_options::<rhai::Array>(
+            CallFnOptions::new().with_tag(world),
+            &mut scope,
+            &ast,
+            "query",
+            (Dynamic::from(TileCoord::RIGHT),),
+        )
+        .unwrap();
+
+    assert_eq!(result[0].as_int().unwrap(), INT::from(tile));
+    assert_eq!(result[1].as_int().unwrap(), 4);
+
+    let neighbors = result[2].clone().into_typed_array::<TileCoord>().unwrap();
+    assert_eq!(neighbors, vec![TileCoord::RIGHT]);
+
+    // outside of a tick there is nothing to query
+    let result = resource_man
+        .engine
+        .call_fn::<rhai::Array>(
+            &mut scope,
+            &ast,
+            "query",
+            (Dynamic::from(TileCoord::RIGHT),),
+        )
+        .unwrap();
+
+    assert!(result[0].is_unit());
+    assert!(result[2].clone().into_array().unwrap().is_empty());
+}
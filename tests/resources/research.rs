@@ -0,0 +1,130 @@
+use std::fs;
+
+use automancy_defs::id::Id;
+use automancy_resources::data::inventory::Inventory;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+use crate::common::{add_item, add_tile};
+
+static BASE: &str = r#"(
+    id: "test:base",
+    icon: "test:still",
+    next: None,
+    name: "test:base_name",
+    description: "test:base_description",
+    required_items: [("test:coal", 4)],
+)"#;
+
+static STILL: &str = r#"(
+    id: "test:still",
+    icon: "test:still",
+    next: None,
+    name: "test:still_name",
+    description: "test:still_description",
+    depends_on: ["test:base"],
+    unlocks: ["test:still"],
+)"#;
+
+/// Writes the researches into a resource folder of their own, and loads them with a coal item and a still tile registered.
+fn load(name: &str, researches: &[&str]) -> (ResourceManager, anyhow::Result<()>) {
+    let dir = std::env::temp_dir().join(format!("automancy_test_{name}"));
+    let research_dir = dir.join("researches");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&research_dir).unwrap();
+
+    for (i, research) in researches.iter().enumerate() {
+        fs::write(research_dir.join(format!("{i}.ron")), research).unwrap();
+    }
+
+    let mut resource_man = crate::common::resource_man();
+
+    add_item(&mut resource_man, "test:coal");
+    add_tile(&mut resource_man, "test:still");
+
+    resource_man.load_researches(&dir).unwrap();
+    let result = resource_man.compile_researches();
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    (resource_man, result)
+}
+
+fn id(resource_man: &ResourceManager, id: &str) -> Id {
+    resource_man.interner.get(id).unwrap()
+}
+
+#[test]
+fn test_research_unlocking() {
+    let (resource_man, result) = load("research_unlocking", &[BASE, STILL]);
+    result.unwrap();
+
+    let base = id(&resource_man, "test:base");
+    let still = id(&resource_man, "test:still");
+    let coal = id(&resource_man, "test:coal");
+
+    let mut game_data = DataMap::default();
+    let mut inventory = Inventory::default();
+    inventory.insert(coal, 3);
+    game_data.insert(
+        resource_man.registry.data_ids.player_inventory,
+        Data::Inventory(inventory),
+    );
+
+    assert!(!resource_man.is_unlocked(still, &game_data));
+    // the still depends on the base research
+    assert!(!resource_man.unlock_research(still, &mut game_data));
+    // not enough coal
+    assert!(!resource_man.unlock_research(base, &mut game_data));
+
+    if let Some(Data::Inventory(inventory)) =
+        game_data.get_mut(&resource_man.registry.data_ids.player_inventory)
+    {
+        inventory.insert(coal, 5);
+    }
+
+    assert!(resource_man.unlock_research(base, &mut game_data));
+    assert!(resource_man.unlock_research(still, &mut game_data));
+    assert!(resource_man.is_unlocked(still, &game_data));
+
+    if let Some(Data::Inventory(inventory)) =
+        game_data.get_mut(&resource_man.registry.data_ids.player_inventory)
+    {
+        assert_eq!(inventory.get(coal), 1);
+    }
+}
+
+#[test]
+fn test_research_validation() {
+    let unknown_dependency = STILL.replace("test:base", "test:missing");
+    let (_, result) = load("research_unknown_dependency", &[&unknown_dependency]);
+    assert!(result.is_err());
+
+    let unknown_unlock =
+        STILL.replace(r#"unlocks: ["test:still"]"#, r#"unlocks: ["test:nothing"]"#);
+    let (_, result) = load("research_unknown_unlock", &[BASE, &unknown_unlock]);
+    assert!(result.is_err());
+
+    let cyclic_base = BASE.replace(
+        "next: None",
+        r#"next: Some(["test:still"]), depends_on: ["test:still"]"#,
+    );
+    let (_, result) = load("research_cycle", &[&cyclic_base, STILL]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_deprecated_unlock() {
+    // researches written before unlocks still load
+    let deprecated = STILL.replace(r#"unlocks: ["test:still"]"#, r#"unlock: "test:still""#);
+    let (resource_man, result) = load("research_deprecated_unlock", &[BASE, &deprecated]);
+    result.unwrap();
+
+    let still = id(&resource_man, "test:still");
+    assert!(resource_man.get_research_by_unlock(still).is_some());
+    assert!(resource_man
+        .get_research(still)
+        .unwrap()
+        .unlocks
+        .contains(&still));
+}
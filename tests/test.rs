@@ -1,6 +1,13 @@
+use rand::RngCore;
+
+use automancy::tile_entity::tile_rng;
 use automancy_defs::coord::TileCoord;
 
+pub mod common;
+pub mod game;
 pub mod macros;
+pub mod map;
+pub mod resources;
 
 #[test]
 fn test_tile_coord_serde() {
@@ -12,3 +19,17 @@ fn test_tile_coord_serde() {
 
     assert_eq!(c, deserialized);
 }
+
+#[test]
+fn test_tile_rng_deterministic() {
+    let coord = TileCoord::new(3, -7);
+
+    let a = tile_rng(42, coord, 10).next_u32();
+    let b = tile_rng(42, coord, 10).next_u32();
+
+    assert_eq!(a, b);
+    assert_ne!(a, tile_rng(42, coord, 11).next_u32());
+    assert_ne!(a, tile_rng(42, coord, 10 + (1 << 16)).next_u32());
+    assert_ne!(a, tile_rng(42, TileCoord::new(-7, 3), 10).next_u32());
+    assert_ne!(a, tile_rng(43, coord, 10).next_u32());
+}